@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use futures::channel::mpsc::UnboundedSender;
+use futures::StreamExt;
+
+use crate::error::RobinhoodErr;
+use crate::queries::QuotesResponse;
+use crate::Robinhood;
+
+/// A condition on the quote of a symbol, see `Alerts`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertRule {
+    /// The price goes from below to at or above the level
+    CrossesAbove(f64),
+    /// The price goes from above to at or below the level
+    CrossesBelow(f64),
+    /// The price moved at least this many percent away from `previous_close`, either way
+    MovePercent(f64),
+    /// `ask_price - bid_price` is wider than this many dollars
+    SpreadAbove(f64),
+    /// Trading was halted or resumed
+    HaltToggled,
+}
+
+/// A rule that fired
+#[derive(Debug, Clone)]
+pub struct AlertEvent {
+    /// As returned by `Alerts::add`
+    pub id: usize,
+    pub symbol: String,
+    pub rule: AlertRule,
+    /// The quote the rule fired on
+    pub quote: QuotesResponse,
+}
+
+#[derive(Debug)]
+struct Alert {
+    id: usize,
+    symbol: String,
+    rule: AlertRule,
+    // Whether the condition held on the previous quote, `None` before the first one
+    active: Option<bool>,
+}
+
+/// Where `Robinhood::run_alerts` delivers events
+pub enum AlertSink {
+    Channel(UnboundedSender<AlertEvent>),
+    Callback(Box<dyn FnMut(AlertEvent) + Send>),
+}
+
+impl AlertSink {
+    pub fn callback<F: FnMut(AlertEvent) + Send + 'static>(callback: F) -> Self {
+        AlertSink::Callback(Box::new(callback))
+    }
+
+    // `false` once nobody listens anymore
+    fn deliver(&mut self, event: AlertEvent) -> bool {
+        match self {
+            AlertSink::Channel(sender) => sender.unbounded_send(event).is_ok(),
+            AlertSink::Callback(callback) => {
+                callback(event);
+                true
+            }
+        }
+    }
+}
+
+impl From<UnboundedSender<AlertEvent>> for AlertSink {
+    fn from(sender: UnboundedSender<AlertEvent>) -> Self {
+        AlertSink::Channel(sender)
+    }
+}
+
+/// Alert rules by symbol and the state needed to detect their transitions
+///
+/// Rules fire when their condition starts to hold and fire again only after it stopped
+/// holding in between. Crossings need a quote on the other side of the level first,
+/// `MovePercent` and `SpreadAbove` also fire on the first quote when it already holds.
+/// The price is `last_extended_hours_trade_price` when there is one, `last_trade_price`
+/// otherwise.
+#[derive(Debug, Default)]
+pub struct Alerts {
+    alerts: Vec<Alert>,
+    next_id: usize,
+}
+
+fn parse(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok()
+}
+
+fn price(quote: &QuotesResponse) -> Option<f64> {
+    match &quote.last_extended_hours_trade_price {
+        Some(price) => parse(price),
+        None => parse(&quote.last_trade_price),
+    }
+}
+
+impl Alerts {
+    pub fn new() -> Self {
+        Alerts::default()
+    }
+
+    /// Registers a rule for `symbol` and returns its id
+    pub fn add(&mut self, symbol: &str, rule: AlertRule) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.alerts.push(Alert {
+            id,
+            symbol: symbol.to_uppercase(),
+            rule,
+            active: None,
+        });
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        self.alerts.retain(|alert| alert.id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    /// Every symbol with at least one rule
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.alerts.iter().map(|a| a.symbol.clone()).collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Evaluates the rules of `quote.symbol` and returns the ones that fired
+    pub fn evaluate(&mut self, quote: &QuotesResponse) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for alert in self.alerts.iter_mut().filter(|a| a.symbol == quote.symbol) {
+            let holds = match alert.rule {
+                AlertRule::CrossesAbove(level) => match price(quote) {
+                    Some(price) => price >= level,
+                    None => continue,
+                },
+                AlertRule::CrossesBelow(level) => match price(quote) {
+                    Some(price) => price <= level,
+                    None => continue,
+                },
+                AlertRule::MovePercent(percent) => {
+                    match (price(quote), parse(&quote.previous_close)) {
+                        (Some(price), Some(close)) if close > 0.0 => {
+                            (price / close - 1.0).abs() * 100.0 >= percent
+                        }
+                        _ => continue,
+                    }
+                }
+                AlertRule::SpreadAbove(spread) => {
+                    match (parse(&quote.ask_price), parse(&quote.bid_price)) {
+                        (Some(ask), Some(bid)) => ask - bid > spread,
+                        _ => continue,
+                    }
+                }
+                AlertRule::HaltToggled => quote.trading_halted,
+            };
+            let fires = match (alert.rule, alert.active) {
+                (AlertRule::HaltToggled, Some(halted)) => halted != holds,
+                (_, Some(active)) => holds && !active,
+                (AlertRule::MovePercent(_) | AlertRule::SpreadAbove(_), None) => holds,
+                (_, None) => false,
+            };
+            alert.active = Some(holds);
+            if fires {
+                events.push(AlertEvent {
+                    id: alert.id,
+                    symbol: alert.symbol.clone(),
+                    rule: alert.rule,
+                    quote: quote.clone(),
+                });
+            }
+        }
+        events
+    }
+}
+
+impl Robinhood {
+    /// Evaluates `alerts` on every quote change and delivers the events to `sink`
+    ///
+    /// Quotes come from `quote_stream` so the alerts share the poller of the session.
+    /// Runs until the channel of the sink is closed, or returns the first error. The
+    /// state of the rules is kept in `alerts`, call again to resume after an error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::time::Duration;
+    /// use futures::channel::mpsc;
+    /// use robinhood::{AlertRule, AlertSink, Alerts};
+    ///
+    /// let mut alerts = Alerts::new();
+    /// alerts.add("SPY", AlertRule::CrossesAbove(400.0));
+    /// alerts.add("SPY", AlertRule::MovePercent(2.0));
+    /// alerts.add("GME", AlertRule::HaltToggled);
+    ///
+    /// // With a callback
+    /// let sink = AlertSink::callback(|event| println!("{} {:?}", event.symbol, event.rule));
+    /// robinhood_client.run_alerts(&mut alerts, Duration::from_secs(5), sink).await?;
+    ///
+    /// // Or with a channel
+    /// let (sender, mut events) = mpsc::unbounded();
+    /// let run = robinhood_client.run_alerts(&mut alerts, Duration::from_secs(5), sender);
+    /// ```
+    pub async fn run_alerts<S: Into<AlertSink>>(
+        &self,
+        alerts: &mut Alerts,
+        interval: Duration,
+        sink: S,
+    ) -> Result<(), RobinhoodErr> {
+        let mut sink = sink.into();
+        let symbols = alerts.symbols();
+        let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        let mut quotes = self.quote_stream(&symbols, interval);
+        while let Some(quote) = quotes.next().await {
+            for event in alerts.evaluate(&quote?) {
+                if !sink.deliver(event) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(price: &str, bid: &str, ask: &str, halted: bool) -> QuotesResponse {
+        QuotesResponse {
+            symbol: "SPY".to_owned(),
+            last_trade_price: price.to_owned(),
+            previous_close: "100".to_owned(),
+            bid_price: bid.to_owned(),
+            ask_price: ask.to_owned(),
+            trading_halted: halted,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rules_fire_on_transitions() {
+        let mut alerts = Alerts::new();
+        let above = alerts.add("spy", AlertRule::CrossesAbove(101.0));
+        let below = alerts.add("SPY", AlertRule::CrossesBelow(99.0));
+        let moved = alerts.add("SPY", AlertRule::MovePercent(2.0));
+        let spread = alerts.add("SPY", AlertRule::SpreadAbove(0.5));
+        let halt = alerts.add("SPY", AlertRule::HaltToggled);
+        alerts.add("QQQ", AlertRule::CrossesAbove(1.0));
+        assert_eq!(alerts.symbols(), vec!["QQQ", "SPY"]);
+
+        let fired = |alerts: &mut Alerts, quote: QuotesResponse| -> Vec<usize> {
+            alerts
+                .evaluate(&quote)
+                .iter()
+                .map(|event| event.id)
+                .collect()
+        };
+        // Already above on the first quote, which is no crossing
+        assert_eq!(
+            fired(&mut alerts, quote("101.5", "101.4", "102", false)),
+            vec![spread]
+        );
+        assert!(fired(&mut alerts, quote("100", "99.9", "100.1", false)).is_empty());
+        assert_eq!(
+            fired(&mut alerts, quote("101", "100.9", "101.1", false)),
+            vec![above]
+        );
+        assert_eq!(
+            fired(&mut alerts, quote("98", "97.9", "98.1", true)),
+            vec![below, moved, halt]
+        );
+        // Still moved and halted
+        assert!(fired(&mut alerts, quote("97", "96.9", "97.1", true)).is_empty());
+        assert_eq!(
+            fired(&mut alerts, quote("97", "96.9", "97.1", false)),
+            vec![halt]
+        );
+
+        alerts.remove(halt);
+        assert!(fired(&mut alerts, quote("97", "96.9", "97.1", true)).is_empty());
+    }
+}
@@ -0,0 +1,524 @@
+//! The `robinhood` command line tool, built with `--features cli`
+//!
+//! ```text
+//! robinhood login
+//! robinhood quote SPY AAPL
+//! robinhood buy SPY 1 --limit 380
+//! robinhood history SPY --span month --json
+//! ```
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Args, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use robinhood::blocking::{self, Robinhood};
+use robinhood::{Interval, Order, OrderRequest, Position, RobinhoodErr, Side, Span};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "robinhood", version, about = "Robinhood from the command line")]
+struct Cli {
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+    /// Session file, defaults to $ROBINHOOD_SESSION or ~/.robinhood/session.json
+    #[arg(long, global = true)]
+    session: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Log in with an MFA code and save the session
+    Login {
+        #[arg(long)]
+        username: Option<String>,
+    },
+    /// Latest quote of each symbol
+    Quote {
+        #[arg(required = true)]
+        symbols: Vec<String>,
+    },
+    /// Open stock positions
+    Positions,
+    /// List or cancel orders
+    Orders {
+        #[command(subcommand)]
+        command: OrdersCommand,
+    },
+    /// Buy shares after confirmation
+    Buy(Trade),
+    /// Sell shares after confirmation
+    Sell(Trade),
+    /// Historical prices of a symbol
+    History {
+        symbol: String,
+        /// hour, day, week, month, 3month, year or 5year
+        #[arg(long, default_value = "year", value_parser = parse_span)]
+        span: Span,
+        /// 5minute, 10minute, hour, day or week, picked from the span when left out
+        #[arg(long, value_parser = parse_interval)]
+        interval: Option<Interval>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum OrdersCommand {
+    /// Most recent orders, newest first
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Cancel an open order
+    Cancel { id: String },
+}
+
+#[derive(Args, Clone)]
+struct Trade {
+    symbol: String,
+    quantity: f64,
+    /// Limit price, a market order is sent when left out
+    #[arg(long)]
+    limit: Option<f64>,
+    /// Don't ask for confirmation
+    #[arg(long, short)]
+    yes: bool,
+}
+
+const SPANS: [Span; 7] = [
+    Span::Hour,
+    Span::Day,
+    Span::Week,
+    Span::Month,
+    Span::ThreeMonth,
+    Span::Year,
+    Span::FiveYear,
+];
+const INTERVALS: [Interval; 5] = [
+    Interval::FiveMinute,
+    Interval::TenMinute,
+    Interval::Hour,
+    Interval::Day,
+    Interval::Week,
+];
+
+fn parse_span(value: &str) -> Result<Span, String> {
+    SPANS
+        .iter()
+        .find(|span| span.as_str() == value)
+        .copied()
+        .ok_or_else(|| format!("unknown span {}", value))
+}
+
+fn parse_interval(value: &str) -> Result<Interval, String> {
+    INTERVALS
+        .iter()
+        .find(|interval| interval.as_str() == value)
+        .copied()
+        .ok_or_else(|| format!("unknown interval {}", value))
+}
+
+// The finest interval Robinhood serves for a span
+fn default_interval(span: Span) -> Interval {
+    match span {
+        Span::Hour | Span::Day => Interval::FiveMinute,
+        Span::Week => Interval::TenMinute,
+        Span::Month | Span::ThreeMonth => Interval::Hour,
+        Span::Year => Interval::Day,
+        Span::FiveYear => Interval::Week,
+    }
+}
+
+// Tokens of a logged in session, reused by every command
+#[derive(Serialize, Deserialize)]
+struct Session {
+    token: String,
+    refresh_token: String,
+    device_token: Uuid,
+}
+
+fn session_path(path: Option<PathBuf>) -> CliResult<PathBuf> {
+    if let Some(path) = path.or_else(|| env::var_os("ROBINHOOD_SESSION").map(PathBuf::from)) {
+        return Ok(path);
+    }
+    match session_dir() {
+        Some(dir) => Ok(dir.join("session.json")),
+        None => Err("no HOME directory, pass --session".into()),
+    }
+}
+
+// Directory of the default session, only ever holds our own files
+fn session_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| Path::new(&home).join(".robinhood"))
+}
+
+fn save_session(path: &Path, client: &Robinhood) -> CliResult<()> {
+    let session = Session {
+        token: client.get_token(),
+        refresh_token: client.get_refresh_token(),
+        device_token: client.get_device_token(),
+    };
+    if let Some(dir) = path.parent() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(dir)?;
+        // Like the file below, an existing directory keeps its mode otherwise. Only our own
+        // directory is tightened, the parent of a --session path may be shared like /tmp
+        #[cfg(unix)]
+        if session_dir().as_deref() == Some(dir) {
+            fs::set_permissions(dir, std::os::unix::fs::PermissionsExt::from_mode(0o700))?;
+        }
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, a session saved before may be readable by others
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(serde_json::to_string_pretty(&session)?.as_bytes())?;
+    Ok(())
+}
+
+fn load_session(path: &Path) -> CliResult<Robinhood> {
+    let session: Session = match fs::read_to_string(path) {
+        Ok(session) => serde_json::from_str(&session)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err("not logged in, run `robinhood login` first".into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(blocking::token_login(
+        session.token,
+        session.refresh_token,
+        session.device_token,
+    ))
+}
+
+fn prompt(message: &str) -> CliResult<String> {
+    eprint!("{}", message);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_owned())
+}
+
+fn print_json<T: Serialize>(value: &T) -> CliResult<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// Robinhood sends prices as strings with 6 decimals
+fn price(value: &str) -> String {
+    match value.parse::<f64>() {
+        Ok(value) => format!("{:.2}", value),
+        Err(_) => value.to_owned(),
+    }
+}
+
+// The serialized name of an enum such as `Side` or `OrderState`
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(label)) => label,
+        _ => String::new(),
+    }
+}
+
+// Symbols of instrument URLs, each looked up once
+struct Symbols<'a> {
+    client: &'a Robinhood,
+    cache: HashMap<String, String>,
+}
+
+impl Symbols<'_> {
+    fn of(&mut self, instrument: &str) -> String {
+        let client = self.client;
+        self.cache
+            .entry(instrument.to_owned())
+            .or_insert_with(|| match client.get_instrument(instrument) {
+                Ok(instrument) => instrument.symbol,
+                Err(_) => instrument.to_owned(),
+            })
+            .clone()
+    }
+}
+
+#[derive(Serialize)]
+struct WithSymbol<'a, T> {
+    symbol: String,
+    #[serde(flatten)]
+    item: &'a T,
+}
+
+fn login(path: &Path, username: Option<String>) -> CliResult<()> {
+    let username = match username {
+        Some(username) => username,
+        None => prompt("Username: ")?,
+    };
+    let password = rpassword::prompt_password("Password: ")?;
+    let mfa_client = blocking::mfa_login(username, password)?;
+    let mfa_code = prompt("MFA code: ")?;
+    let client = mfa_client.log_in(mfa_code)?;
+    save_session(path, &client)?;
+    eprintln!("Logged in, session saved to {}", path.display());
+    Ok(())
+}
+
+fn quote(client: &Robinhood, symbols: &[String], json: bool) -> CliResult<()> {
+    let quotes = symbols
+        .iter()
+        .map(|symbol| client.get_quote(symbol))
+        .collect::<Result<Vec<_>, _>>()?;
+    if json {
+        return print_json(&quotes);
+    }
+    println!(
+        "{:<8} {:>10} {:>10} {:>10} {:>8}",
+        "SYMBOL", "LAST", "BID", "ASK", "CHANGE"
+    );
+    for quote in &quotes {
+        let last = quote.last_trade_price.parse::<f64>()?;
+        let previous = quote.previous_close.parse::<f64>()?;
+        let change = if previous > 0.0 {
+            format!("{:+.2}%", (last / previous - 1.0) * 100.0)
+        } else {
+            String::new()
+        };
+        println!(
+            "{:<8} {:>10.2} {:>10} {:>10} {:>8}",
+            quote.symbol,
+            last,
+            price(&quote.bid_price),
+            price(&quote.ask_price),
+            change
+        );
+    }
+    Ok(())
+}
+
+fn positions(client: &Robinhood, json: bool) -> CliResult<()> {
+    let positions = client.get_positions()?;
+    let mut symbols = Symbols {
+        client,
+        cache: HashMap::new(),
+    };
+    let rows: Vec<WithSymbol<Position>> = positions
+        .iter()
+        .map(|position| WithSymbol {
+            symbol: symbols.of(&position.instrument),
+            item: position,
+        })
+        .collect();
+    if json {
+        return print_json(&rows);
+    }
+    println!("{:<8} {:>14} {:>12}", "SYMBOL", "QUANTITY", "AVG PRICE");
+    for row in &rows {
+        println!(
+            "{:<8} {:>14} {:>12}",
+            row.symbol,
+            row.item.quantity.parse::<f64>()?,
+            price(&row.item.average_buy_price)
+        );
+    }
+    Ok(())
+}
+
+fn orders(client: &Robinhood, command: OrdersCommand, json: bool) -> CliResult<()> {
+    match command {
+        OrdersCommand::List { limit } => {
+            let orders = client.get_orders(Some(limit))?;
+            let mut symbols = Symbols {
+                client,
+                cache: HashMap::new(),
+            };
+            let rows: Vec<WithSymbol<Order>> = orders
+                .iter()
+                .map(|order| WithSymbol {
+                    symbol: symbols.of(&order.instrument),
+                    item: order,
+                })
+                .collect();
+            if json {
+                return print_json(&rows);
+            }
+            for row in &rows {
+                print_order(&row.symbol, row.item);
+            }
+        }
+        OrdersCommand::Cancel { id } => {
+            client.cancel_order(&id)?;
+            if json {
+                return print_json(&serde_json::json!({ "id": id, "cancel_requested": true }));
+            }
+            println!("Cancellation requested for {}", id);
+        }
+    }
+    Ok(())
+}
+
+fn print_order(symbol: &str, order: &Order) {
+    println!(
+        "{:<27} {:<4} {:<8} {:<6} {:>10} {:>10} {:<12} {}",
+        order.created_at,
+        label(&order.side),
+        symbol,
+        label(&order.order_type),
+        order.quantity.parse::<f64>().unwrap_or_default(),
+        order.price.as_deref().map(price).unwrap_or_default(),
+        label(&order.state),
+        order.id
+    );
+}
+
+fn trade(client: &Robinhood, side: Side, trade: Trade, json: bool) -> CliResult<()> {
+    let symbol = trade.symbol.to_uppercase();
+    let quote = client.get_quote(&symbol)?;
+    let request = match trade.limit {
+        Some(limit) => OrderRequest::limit(&symbol, side, trade.quantity, limit),
+        None => OrderRequest::market(&symbol, side, trade.quantity),
+    };
+    if !trade.yes {
+        let at = match trade.limit {
+            Some(limit) => format!("limit {:.2}", limit),
+            None => "market".to_owned(),
+        };
+        let question = format!(
+            "{} {} {} at {} (last {})? [y/N] ",
+            label(&side),
+            trade.quantity,
+            symbol,
+            at,
+            price(&quote.last_trade_price)
+        );
+        if !prompt(&question)?.eq_ignore_ascii_case("y") {
+            eprintln!("Not sent");
+            return Ok(());
+        }
+    }
+    let order = client.place_order(&request)?;
+    if json {
+        return print_json(&order);
+    }
+    print_order(&symbol, &order);
+    Ok(())
+}
+
+fn history(
+    client: &Robinhood,
+    symbol: &str,
+    span: Span,
+    interval: Option<Interval>,
+    json: bool,
+) -> CliResult<()> {
+    let interval = interval.unwrap_or_else(|| default_interval(span));
+    let points = client.get_historicals(symbol, interval, span)?;
+    if json {
+        return print_json(&points);
+    }
+    println!(
+        "{:<21} {:>10} {:>10} {:>10} {:>10} {:>12}",
+        "BEGINS AT", "OPEN", "HIGH", "LOW", "CLOSE", "VOLUME"
+    );
+    for point in &points {
+        println!(
+            "{:<21} {:>10} {:>10} {:>10} {:>10} {:>12}",
+            point.begins_at,
+            price(&point.open_price),
+            price(&point.high_price),
+            price(&point.low_price),
+            price(&point.close_price),
+            point.volume
+        );
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> CliResult<()> {
+    let path = session_path(cli.session)?;
+    if let Command::Login { username } = cli.command {
+        return login(&path, username);
+    }
+    let mut client = load_session(&path)?;
+    match execute(&client, cli.command.clone(), cli.json) {
+        // The access token expired, the refresh token of the session gets a new one
+        Err(e) if is_unauthorized(e.as_ref()) => {
+            client.refresh_token(None)?;
+            save_session(&path, &client)?;
+            execute(&client, cli.command, cli.json)
+        }
+        result => result,
+    }
+}
+
+fn is_unauthorized(e: &(dyn Error + 'static)) -> bool {
+    matches!(
+        e.downcast_ref::<RobinhoodErr>(),
+        Some(RobinhoodErr::Unauthorized(_))
+    )
+}
+
+fn execute(client: &Robinhood, command: Command, json: bool) -> CliResult<()> {
+    match command {
+        Command::Login { .. } => unreachable!(),
+        Command::Quote { symbols } => quote(client, &symbols, json),
+        Command::Positions => positions(client, json),
+        Command::Orders { command } => orders(client, command, json),
+        Command::Buy(order) => trade(client, Side::Buy, order, json),
+        Command::Sell(order) => trade(client, Side::Sell, order, json),
+        Command::History {
+            symbol,
+            span,
+            interval,
+        } => history(client, &symbol, span, interval, json),
+    }
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spans_and_intervals() {
+        assert_eq!(parse_span("3month"), Ok(Span::ThreeMonth));
+        assert_eq!(parse_span("5year"), Ok(Span::FiveYear));
+        assert!(parse_span("decade").is_err());
+        assert_eq!(parse_interval("5minute"), Ok(Interval::FiveMinute));
+        assert_eq!(parse_interval("week"), Ok(Interval::Week));
+        assert!(parse_interval("second").is_err());
+        for span in SPANS {
+            assert_eq!(parse_span(span.as_str()), Ok(span));
+        }
+        for interval in INTERVALS {
+            assert_eq!(parse_interval(interval.as_str()), Ok(interval));
+        }
+    }
+
+    #[test]
+    fn default_intervals_are_the_finest_served() {
+        assert_eq!(default_interval(Span::Day), Interval::FiveMinute);
+        assert_eq!(default_interval(Span::Week), Interval::TenMinute);
+        assert_eq!(default_interval(Span::ThreeMonth), Interval::Hour);
+        assert_eq!(default_interval(Span::Year), Interval::Day);
+        assert_eq!(default_interval(Span::FiveYear), Interval::Week);
+    }
+}
@@ -0,0 +1,262 @@
+//! A synchronous client for scripts that don't run an async runtime
+//!
+//! Each client owns a single threaded tokio runtime and blocks on the calls of the async
+//! client, with the same types and errors. The configuration methods of the async client
+//! (`set_risk_policy`, `set_rate_limits`, `set_transport`...) are reached through `Deref`.
+//! Don't use it from inside an async runtime, blocking there panics.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mfa_client = robinhood::blocking::mfa_login(username, password)?;
+//! let mfa_code = ...
+//! let robinhood_client = mfa_client.log_in(mfa_code)?;
+//! let price = robinhood_client.get_price("SPY")?;
+//! ```
+use std::ops::{Deref, DerefMut, RangeBounds};
+use std::sync::Arc;
+
+use tokio::runtime::{Builder, Runtime};
+use uuid::Uuid;
+
+use crate::error::{LoginErr, RefreshTokenErr};
+use crate::login::NewToken;
+use crate::{
+    Account, AchRelationship, AggregateOptionPosition, CryptoAccount, CryptoHolding, CryptoOrder,
+    CryptoOrderRequest, CryptoQuote, CurrencyPair, DayTradeSummary, Dividend, HistoricalDataPoint,
+    HttpTransport, Instrument, Interval, Middleware, OptionChain, OptionInstrument,
+    OptionMarketData, OptionOrder, OptionOrderRequest, OptionPosition, OptionType, Order,
+    OrderRequest, Position, QuotesResponse, RateLimits, RobinhoodErr, Span, Transfer,
+    TransferConfirmation, TransferRequest, ValuedOptionPosition, Watchlist, WatchlistItem,
+};
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the tokio runtime of the blocking client")
+}
+
+/// Blocking version of `robinhood::mfa_login`
+pub fn mfa_login(username: String, password: String) -> Result<MfaLogin, RobinhoodErr> {
+    let mfa_client = MfaLogin::new(username, password);
+    mfa_client.request_mfa_code()?;
+    Ok(mfa_client)
+}
+
+/// Blocking version of `robinhood::token_login`
+pub fn token_login(token: String, refresh_token: String, device_token: Uuid) -> Robinhood {
+    let runtime = runtime();
+    let inner = runtime.block_on(crate::Robinhood::token_login(
+        token,
+        refresh_token,
+        device_token,
+    ));
+    Robinhood { inner, runtime }
+}
+
+/// Blocking version of `robinhood::MfaLogin`
+pub struct MfaLogin {
+    inner: crate::MfaLogin,
+    runtime: Runtime,
+}
+
+impl MfaLogin {
+    pub fn new(username: String, password: String) -> Self {
+        MfaLogin {
+            inner: crate::MfaLogin::new(username, password),
+            runtime: runtime(),
+        }
+    }
+
+    pub fn request_mfa_code(&self) -> Result<(), RobinhoodErr> {
+        self.runtime.block_on(self.inner.request_mfa_code())
+    }
+
+    pub fn log_in(self, mfa_code: String) -> Result<Robinhood, LoginErr> {
+        let inner = self.runtime.block_on(self.inner.log_in(mfa_code))?;
+        Ok(Robinhood {
+            inner,
+            runtime: self.runtime,
+        })
+    }
+
+    pub fn set_transport(&mut self, transport: Arc<dyn HttpTransport>) {
+        self.inner.set_transport(transport);
+    }
+
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.inner.add_middleware(middleware);
+    }
+
+    pub fn set_rate_limits(&mut self, limits: RateLimits) {
+        self.inner.set_rate_limits(limits);
+    }
+
+    pub fn set_credentials(&mut self, username: String, password: String) {
+        self.inner.set_credentials(username, password);
+    }
+
+    pub fn change_device_token(&mut self, device_token: Uuid) {
+        self.inner.change_device_token(device_token);
+    }
+
+    pub fn change_agent(&mut self, user_agent: String) {
+        self.inner.change_agent(user_agent);
+    }
+}
+
+/// Blocking version of `robinhood::Robinhood`
+pub struct Robinhood {
+    inner: crate::Robinhood,
+    runtime: Runtime,
+}
+
+// Mirrors `&self` methods of the async client
+macro_rules! blocking {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            pub fn $name(&self $(, $arg: $ty)*) -> Result<$ret, RobinhoodErr> {
+                self.runtime.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+impl Robinhood {
+    /// The async client, to use with your own runtime
+    pub fn into_async(self) -> crate::Robinhood {
+        self.inner
+    }
+
+    pub fn refresh_token(
+        &mut self,
+        old_refresh_token: Option<String>,
+    ) -> Result<Option<NewToken>, RefreshTokenErr> {
+        self.runtime
+            .block_on(self.inner.refresh_token(old_refresh_token))
+    }
+
+    pub fn find_options<R: RangeBounds<f64>>(
+        &self,
+        symbol: &str,
+        expiration: &str,
+        option_type: Option<OptionType>,
+        strikes: R,
+    ) -> Result<Vec<OptionInstrument>, RobinhoodErr> {
+        self.runtime.block_on(
+            self.inner
+                .find_options(symbol, expiration, option_type, strikes),
+        )
+    }
+
+    blocking! {
+        fn get_quote(&self, symbol: &str) -> QuotesResponse;
+        fn get_quotes(&self, symbols: &[&str]) -> Vec<QuotesResponse>;
+        fn get_price(&self, symbol: &str) -> f64;
+        fn get_account(&self) -> Account;
+        fn get_positions(&self) -> Vec<Position>;
+        fn get_position_quantity(&self, instrument: &str) -> f64;
+        fn get_instrument(&self, instrument: &str) -> Instrument;
+        fn get_instruments(&self, ids: &[&str]) -> Vec<Instrument>;
+        fn get_historicals(&self, symbol: &str, interval: Interval, span: Span)
+            -> Vec<HistoricalDataPoint>;
+        fn place_order(&self, order: &OrderRequest) -> Order;
+        fn find_order_by_ref_id(&self, ref_id: Uuid) -> Option<Order>;
+        fn get_orders(&self, limit: Option<usize>) -> Vec<Order>;
+        fn get_order(&self, order_id: &str) -> Order;
+        fn cancel_order(&self, order_id: &str) -> ();
+        fn get_day_trades(&self) -> DayTradeSummary;
+        fn day_trades_remaining(&self) -> usize;
+        fn update_paper_orders(&self) -> ();
+        fn get_option_chain(&self, symbol: &str) -> OptionChain;
+        fn get_option_instrument(&self, id: &str) -> OptionInstrument;
+        fn get_option_market_data(&self, ids: &[&str]) -> Vec<OptionMarketData>;
+        fn get_option_positions(&self) -> Vec<OptionPosition>;
+        fn get_aggregate_option_positions(&self) -> Vec<AggregateOptionPosition>;
+        fn get_valued_option_positions(&self) -> Vec<ValuedOptionPosition>;
+        fn place_option_order(&self, order: &OptionOrderRequest) -> OptionOrder;
+        fn find_option_order_by_ref_id(&self, ref_id: Uuid) -> Option<OptionOrder>;
+        fn get_option_orders(&self) -> Vec<OptionOrder>;
+        fn get_option_order(&self, order_id: &str) -> OptionOrder;
+        fn cancel_option_order(&self, order_id: &str) -> ();
+        fn get_currency_pairs(&self) -> Vec<CurrencyPair>;
+        fn get_currency_pair(&self, symbol: &str) -> CurrencyPair;
+        fn get_crypto_quote(&self, symbol: &str) -> CryptoQuote;
+        fn get_crypto_historicals(
+            &self,
+            symbol: &str,
+            interval: Interval,
+            span: Span
+        ) -> Vec<HistoricalDataPoint>;
+        fn get_dividends(&self) -> Vec<Dividend>;
+        fn get_ach_relationships(&self) -> Vec<AchRelationship>;
+        fn get_transfers(&self, limit: Option<usize>) -> Vec<Transfer>;
+        fn prepare_transfer(&self, request: TransferRequest) -> TransferConfirmation;
+        fn initiate_transfer(&self, confirmation: TransferConfirmation) -> Transfer;
+        fn get_watchlists(&self) -> Vec<Watchlist>;
+        fn get_watchlist(&self, name: &str) -> Watchlist;
+        fn create_watchlist(&self, name: &str) -> Watchlist;
+        fn delete_watchlist(&self, list_id: &str) -> ();
+        fn get_watchlist_items(&self, list_id: &str) -> Vec<WatchlistItem>;
+        fn add_to_watchlist(&self, list_id: &str, symbols: &[&str]) -> ();
+        fn remove_from_watchlist(&self, list_id: &str, symbols: &[&str]) -> ();
+        fn reorder_watchlist(&self, list_id: &str, symbols: &[&str]) -> ();
+        fn resolve_watchlist_items(&self, items: &[WatchlistItem]) -> Vec<Instrument>;
+        fn get_watchlist_quotes(&self, list_id: &str) -> Vec<QuotesResponse>;
+        fn get_crypto_account(&self) -> CryptoAccount;
+        fn get_crypto_holdings(&self) -> Vec<CryptoHolding>;
+        fn place_crypto_order(&self, order: &CryptoOrderRequest) -> CryptoOrder;
+        fn get_crypto_orders(&self) -> Vec<CryptoOrder>;
+        fn get_crypto_order(&self, order_id: &str) -> CryptoOrder;
+        fn cancel_crypto_order(&self, order_id: &str) -> ();
+    }
+}
+
+impl Deref for Robinhood {
+    type Target = crate::Robinhood;
+
+    fn deref(&self) -> &crate::Robinhood {
+        &self.inner
+    }
+}
+
+impl DerefMut for Robinhood {
+    fn deref_mut(&mut self) -> &mut crate::Robinhood {
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::{self, BoxFuture, FutureExt};
+
+    use crate::{HttpRequest, HttpResponse, RateLimits};
+
+    struct Empty;
+
+    impl HttpTransport for Empty {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let body = r#"{"results": [], "next": null}"#.to_owned();
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body,
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn runs_without_a_runtime() {
+        let mut client = token_login("token".to_owned(), "refresh".to_owned(), Uuid::new_v4());
+        client.set_transport(Arc::new(Empty));
+        client.set_rate_limits(RateLimits::unlimited());
+        assert!(client.get_crypto_orders().unwrap().is_empty());
+    }
+}
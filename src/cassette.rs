@@ -0,0 +1,361 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use futures::future::{self, BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{strip_query, RobinhoodErr};
+use crate::http::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+use crate::middleware::{redact_body, redact_json};
+use crate::req::ReqKind;
+use crate::trace::REDACTED;
+
+// Response headers never written to a cassette
+const SECRET_HEADERS: [&str; 2] = ["set-cookie", "authorization"];
+// Login fields replaced on top of the ones `redact_json` hides
+const LOGIN_FIELDS: [&str; 2] = ["username", "device_token"];
+// Request fields generated anew on every run, replaced so a replayed request still matches
+const RANDOM_FIELDS: [&str; 1] = ["ref_id"];
+
+// A line of a cassette. Request headers are left out, they only hold the user agent
+// and the token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    method: ReqKind,
+    /// URL without the scheme and host, query included
+    path: String,
+    body: Option<Value>,
+    response: HttpResponse,
+}
+
+impl Interaction {
+    fn matches(&self, method: ReqKind, path: &str, body: &Option<Value>) -> bool {
+        self.method == method && self.path == path && &self.body == body
+    }
+}
+
+fn path_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.find('/').map_or("/", |start| &rest[start..])
+}
+
+// Method, path and body of a request as they are stored in a cassette
+fn scrub_request(request: &HttpRequest) -> (ReqKind, &str, Option<Value>) {
+    let body = request.body.as_ref().map(|body| {
+        let mut body = redact_json(body);
+        if let Some(fields) = body.as_object_mut() {
+            for field in LOGIN_FIELDS.iter().chain(&RANDOM_FIELDS) {
+                if let Some(value) = fields.get_mut(*field) {
+                    *value = Value::String(REDACTED.to_owned());
+                }
+            }
+        }
+        body
+    });
+    (request.method, path_of(&request.url), body)
+}
+
+fn scrub_response(response: &HttpResponse) -> HttpResponse {
+    HttpResponse {
+        status: response.status,
+        url: response.url.clone(),
+        headers: response
+            .headers
+            .iter()
+            .filter(|(name, _)| !SECRET_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+            .cloned()
+            .collect(),
+        body: redact_body(&response.body),
+    }
+}
+
+/// Sends requests with another transport and appends every request and response to a
+/// JSONL cassette, to be served back by `ReplayTransport`
+///
+/// Passwords, MFA codes and tokens are replaced in both bodies and the request headers
+/// are not written at all, so a cassette is safe to commit.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use robinhood::{MfaLogin, RecordingTransport};
+///
+/// let mut mfa_client = MfaLogin::new(username, password);
+/// mfa_client.set_transport(Arc::new(RecordingTransport::new("tests/session.jsonl")?));
+/// mfa_client.request_mfa_code().await?;
+/// let robinhood_client = mfa_client.log_in(mfa_code).await?;
+/// robinhood_client.get_quote("SPY").await?;
+/// ```
+pub struct RecordingTransport {
+    inner: Arc<dyn HttpTransport>,
+    cassette: Mutex<File>,
+}
+
+impl RecordingTransport {
+    /// Records the requests sent over the network to the cassette at `path`, replacing it
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        RecordingTransport::wrap(Arc::new(ReqwestTransport::new()), path)
+    }
+
+    /// Records the requests sent with `inner`
+    pub fn wrap<P: AsRef<Path>>(inner: Arc<dyn HttpTransport>, path: P) -> io::Result<Self> {
+        Ok(RecordingTransport {
+            inner,
+            cassette: Mutex::new(File::create(path)?),
+        })
+    }
+
+    fn record(&self, request: &HttpRequest, response: &HttpResponse) -> io::Result<()> {
+        let (method, path, body) = scrub_request(request);
+        let interaction = Interaction {
+            method,
+            path: path.to_owned(),
+            body,
+            response: scrub_response(response),
+        };
+        let line = serde_json::to_string(&interaction)?;
+        writeln!(self.cassette.lock().unwrap(), "{}", line)
+    }
+}
+
+impl HttpTransport for RecordingTransport {
+    fn send<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+    ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+        async move {
+            let response = self.inner.send(request).await?;
+            self.record(request, &response)
+                .map_err(|e| RobinhoodErr::Transport {
+                    endpoint: strip_query(&request.url).to_owned(),
+                    message: format!("failed to record the response: {}", e),
+                })?;
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+/// Serves the responses of a cassette written by `RecordingTransport`, without any
+/// network access
+///
+/// Requests are matched by method, path and body, secrets and `ref_id` excluded, and
+/// each recorded response is served once in the recorded order. Once they are all used
+/// the last one is served again. A request that was never recorded fails with
+/// `RobinhoodErr::Transport`.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use robinhood::{MfaLogin, RateLimits, ReplayTransport};
+///
+/// let mut mfa_client = MfaLogin::new(username, password);
+/// mfa_client.set_transport(Arc::new(ReplayTransport::from_file("tests/session.jsonl")?));
+/// let mut robinhood_client = mfa_client.log_in("123456".to_owned()).await?;
+/// robinhood_client.set_rate_limits(RateLimits::unlimited());
+/// let quote = robinhood_client.get_quote("SPY").await?;
+/// ```
+pub struct ReplayTransport {
+    // Recorded interactions and whether they were served
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl ReplayTransport {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let interactions = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok((serde_json::from_str(line)?, false)))
+            .collect::<io::Result<_>>()?;
+        Ok(ReplayTransport {
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    fn replay(&self, request: &HttpRequest) -> Result<HttpResponse, RobinhoodErr> {
+        let (method, path, body) = scrub_request(request);
+        let mut interactions = self.interactions.lock().unwrap();
+        let mut matching = interactions
+            .iter_mut()
+            .filter(|(interaction, _)| interaction.matches(method, path, &body));
+        let mut last = None;
+        for (interaction, served) in matching.by_ref() {
+            if !*served {
+                *served = true;
+                return Ok(interaction.response.clone());
+            }
+            last = Some(interaction);
+        }
+        match last {
+            Some(interaction) => Ok(interaction.response.clone()),
+            None => Err(RobinhoodErr::Transport {
+                endpoint: strip_query(&request.url).to_owned(),
+                message: format!("no recorded response for {} {}", method.as_str(), path),
+            }),
+        }
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn send<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+    ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+        future::ready(self.replay(request)).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    use crate::{Account, OrderRequest, QuotesResponse, RateLimits, Robinhood, Side};
+
+    // Answers the token endpoint with a token and anything else with an empty object
+    struct Stub;
+
+    impl HttpTransport for Stub {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let body = if request.url.contains("oauth2/token/") {
+                r#"{"access_token": "secret-access", "refresh_token": "secret-refresh"}"#
+            } else {
+                "{}"
+            };
+            future::ready(Ok(HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: vec![("Set-Cookie".to_owned(), "session=secret".to_owned())],
+                body: body.to_owned(),
+            }))
+            .boxed()
+        }
+    }
+
+    fn request(method: ReqKind, url: &str, body: Option<Value>) -> HttpRequest {
+        HttpRequest {
+            method,
+            url: url.to_owned(),
+            headers: vec![(
+                "Authorization".to_owned(),
+                "Bearer secret-bearer".to_owned(),
+            )],
+            body,
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn records_without_secrets_and_replays() {
+        let path = std::env::temp_dir().join(format!("robinhood-{}.jsonl", uuid::Uuid::new_v4()));
+        let login = |password: &str| {
+            let body = json!({"username": "secret-me", "password": password, "mfa_code": "123456"});
+            request(
+                ReqKind::Post,
+                "https://api.robinhood.com/oauth2/token/",
+                Some(body),
+            )
+        };
+        let quote = request(ReqKind::Get, "https://api.robinhood.com/quotes/SPY/", None);
+
+        let recorder = RecordingTransport::wrap(Arc::new(Stub), &path).unwrap();
+        block_on(recorder.send(&login("secret-password"))).unwrap();
+        block_on(recorder.send(&quote)).unwrap();
+        drop(recorder);
+        let cassette = fs::read_to_string(&path).unwrap();
+        assert_eq!(cassette.lines().count(), 2);
+        assert!(!cassette.contains("secret"));
+
+        let replay = ReplayTransport::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        // Matched whatever the password
+        let response = block_on(replay.send(&login("another-password"))).unwrap();
+        assert!(response.body.contains("refresh_token"));
+        let response = block_on(replay.send(&quote)).unwrap();
+        assert_eq!(response.body, "{}");
+        // Served again once used up
+        assert!(block_on(replay.send(&quote)).is_ok());
+        let other = request(ReqKind::Get, "https://api.robinhood.com/quotes/AAPL/", None);
+        assert!(matches!(
+            block_on(replay.send(&other)),
+            Err(RobinhoodErr::Transport { .. })
+        ));
+    }
+
+    // Answers the account, quote and order endpoints of an order placement
+    struct Broker;
+
+    impl HttpTransport for Broker {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let body = if request.url.contains("accounts/") {
+                json!({ "results": [Account::default()] })
+            } else if request.url.contains("quotes/") {
+                json!(QuotesResponse {
+                    symbol: "SPY".to_owned(),
+                    ask_price: "390.000000".to_owned(),
+                    bid_price: "389.900000".to_owned(),
+                    last_trade_price: "389.950000".to_owned(),
+                    ..Default::default()
+                })
+            } else {
+                let ref_id = &request.body.as_ref().unwrap()["ref_id"];
+                json!({
+                    "id": "6a3e1a3e", "ref_id": ref_id, "url": "", "account": "",
+                    "instrument": "", "cancel": null, "state": "queued", "side": "buy",
+                    "type": "market", "trigger": "immediate", "time_in_force": "gfd",
+                    "price": "390.00", "stop_price": null, "quantity": "1",
+                    "cumulative_quantity": "0", "average_price": null, "fees": "0.00",
+                    "reject_reason": null, "extended_hours": false, "executions": [],
+                    "created_at": "2021-03-04T15:00:00Z", "updated_at": "2021-03-04T15:00:00Z",
+                    "last_transaction_at": "2021-03-04T15:00:00Z",
+                })
+            };
+            future::ready(Ok(HttpResponse {
+                status: 201,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            }))
+            .boxed()
+        }
+    }
+
+    fn client(transport: Arc<dyn HttpTransport>) -> Robinhood {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            uuid::Uuid::new_v4(),
+        ));
+        client.set_transport(transport);
+        client.set_rate_limits(RateLimits::unlimited());
+        client
+    }
+
+    #[test]
+    fn replays_orders_placed_with_another_ref_id() {
+        let path = std::env::temp_dir().join(format!("robinhood-{}.jsonl", uuid::Uuid::new_v4()));
+        let recorded = OrderRequest::market("SPY", Side::Buy, 1.0);
+        let recorder = Arc::new(RecordingTransport::wrap(Arc::new(Broker), &path).unwrap());
+        block_on(client(recorder).place_order(&recorded)).unwrap();
+        let cassette = fs::read_to_string(&path).unwrap();
+        assert!(cassette.contains(r#""ref_id":"<redacted>""#));
+
+        let replay = Arc::new(ReplayTransport::from_file(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        let replayed = OrderRequest::market("SPY", Side::Buy, 1.0);
+        let order = block_on(client(replay).place_order(&replayed)).unwrap();
+        assert_eq!(order.ref_id, Some(recorded.ref_id));
+    }
+}
@@ -0,0 +1,7 @@
+mod holdings;
+mod market;
+mod orders;
+
+pub use holdings::{CostBasis, CryptoAccount, CryptoHolding};
+pub use market::{CryptoQuote, Currency, CurrencyPair, HistoricalDataPoint, Interval, Span};
+pub use orders::{CryptoAmount, CryptoExecution, CryptoOrder, CryptoOrderRequest};
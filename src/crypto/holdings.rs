@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::Currency;
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, CRYPTO_ACCOUNTS_PATH, CRYPTO_HOLDINGS_PATH, NUMMUS_API_URL};
+
+impl Robinhood {
+    /// Calls nummus.robinhood.com/accounts/ and returns the crypto account
+    pub async fn get_crypto_account(&self) -> Result<CryptoAccount, RobinhoodErr> {
+        let url = &format!("{}{}", NUMMUS_API_URL, CRYPTO_ACCOUNTS_PATH);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        let accounts = decode::<CryptoAccountsResponse>(&response)?;
+        match accounts.results.into_iter().next() {
+            Some(account) => Ok(account),
+            None => Err(RobinhoodErr::BadResponseBody(
+                "No crypto account found for this session".to_owned(),
+            )),
+        }
+    }
+
+    /// Calls nummus.robinhood.com/holdings/ and returns every crypto holding with its cost bases
+    pub async fn get_crypto_holdings(&self) -> Result<Vec<CryptoHolding>, RobinhoodErr> {
+        let url = &format!("{}{}", NUMMUS_API_URL, CRYPTO_HOLDINGS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct CryptoAccountsResponse {
+    results: Vec<CryptoAccount>,
+}
+
+// "id": "6a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d",
+// "status": "active",
+// "buying_power": "1520.310000",
+// "created_at": "2019-06-11T14:23:42.121214Z",
+// "updated_at": "2021-03-04T01:00:00.000000Z"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CryptoAccount {
+    pub id: String,
+    pub status: String,
+    pub buying_power: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// "id": "0f1e2d3c-4b5a-4968-8776-5a4b3c2d1e0f",
+// "currency_id": "1072fc76-1862-41ab-82c2-485837590762",
+// "direct_cost_basis": "1021.510000000000000000",
+// "direct_quantity": "0.020134000000000000",
+// "intraday_cost_basis": "0.000000000000000000",
+// "intraday_quantity": "0.000000000000000000",
+// "marked_cost_basis": "0.000000000000000000",
+// "marked_quantity": "0.000000000000000000"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CostBasis {
+    pub id: String,
+    pub currency_id: String,
+    pub direct_cost_basis: String,
+    pub direct_quantity: String,
+    pub intraday_cost_basis: String,
+    pub intraday_quantity: String,
+    pub marked_cost_basis: String,
+    pub marked_quantity: String,
+}
+
+// "id": "2c3d4e5f-6a7b-4c8d-9e0f-1a2b3c4d5e6f",
+// "account_id": "6a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d",
+// "currency": {...},
+// "quantity": "0.020134000000000000",
+// "quantity_available": "0.020134000000000000",
+// "quantity_held_for_buy": "0.000000000000000000",
+// "quantity_held_for_sell": "0.000000000000000000",
+// "cost_bases": [{...}],
+// "created_at": "2021-01-12T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CryptoHolding {
+    pub id: String,
+    pub account_id: String,
+    pub currency: Currency,
+    pub quantity: String,
+    pub quantity_available: String,
+    pub quantity_held_for_buy: String,
+    pub quantity_held_for_sell: String,
+    pub cost_bases: Vec<CostBasis>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl CryptoHolding {
+    /// Sum of the direct cost bases in USD
+    pub fn cost_basis(&self) -> Result<f64, RobinhoodErr> {
+        let mut total = 0.0;
+        for basis in &self.cost_bases {
+            total += basis.direct_cost_basis.parse::<f64>()?;
+        }
+        Ok(total)
+    }
+}
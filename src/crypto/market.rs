@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{
+    Robinhood, CRYPTO_HISTORICALS_PATH, CRYPTO_PAIRS_PATH, CRYPTO_QUOTES_PATH, NUMMUS_API_URL,
+    ROBINHOOD_API_URL,
+};
+
+impl Robinhood {
+    /// Calls nummus.robinhood.com/currency_pairs/ and returns every crypto currency pair
+    pub async fn get_currency_pairs(&self) -> Result<Vec<CurrencyPair>, RobinhoodErr> {
+        let url = &format!("{}{}", NUMMUS_API_URL, CRYPTO_PAIRS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Finds the USD pair of a crypto currency, `symbol` can be "BTC" or "BTC-USD"
+    pub async fn get_currency_pair(&self, symbol: &str) -> Result<CurrencyPair, RobinhoodErr> {
+        let code = symbol.split('-').next().unwrap_or(symbol);
+        let pairs = self.get_currency_pairs().await?;
+        match pairs.into_iter().find(|pair| {
+            pair.asset_currency.code.eq_ignore_ascii_case(code)
+                && pair.quote_currency.code.eq_ignore_ascii_case("USD")
+        }) {
+            Some(pair) => Ok(pair),
+            None => Err(RobinhoodErr::NotFound(format!(
+                "{}{} {}",
+                NUMMUS_API_URL, CRYPTO_PAIRS_PATH, symbol
+            ))),
+        }
+    }
+
+    /// Calls api.robinhood.com/marketdata/forex/quotes/(pair id)/ for the USD pair of `symbol`
+    pub async fn get_crypto_quote(&self, symbol: &str) -> Result<CryptoQuote, RobinhoodErr> {
+        let pair = self.get_currency_pair(symbol).await?;
+        self.get_crypto_quote_by_pair_id(&pair.id).await
+    }
+
+    pub(crate) async fn get_crypto_quote_by_pair_id(
+        &self,
+        pair_id: &str,
+    ) -> Result<CryptoQuote, RobinhoodErr> {
+        let url = &format!("{}{}{}/", ROBINHOOD_API_URL, CRYPTO_QUOTES_PATH, pair_id);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<CryptoQuote>(&response)
+    }
+
+    /// Calls api.robinhood.com/marketdata/forex/historicals/(pair id)/ for the USD pair of `symbol`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{Interval, Span};
+    ///
+    /// let candles = robinhood_client
+    ///     .get_crypto_historicals("ETH", Interval::Hour, Span::Week)
+    ///     .await?;
+    /// ```
+    pub async fn get_crypto_historicals(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        span: Span,
+    ) -> Result<Vec<HistoricalDataPoint>, RobinhoodErr> {
+        let pair = self.get_currency_pair(symbol).await?;
+        let url = &format!(
+            "{}{}{}/?interval={}&span={}&bounds=24_7",
+            ROBINHOOD_API_URL,
+            CRYPTO_HISTORICALS_PATH,
+            pair.id,
+            interval.as_str(),
+            span.as_str()
+        );
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        Ok(decode::<HistoricalsResponse>(&response)?.data_points)
+    }
+}
+
+/// Width of a single historical data point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    FifteenSecond,
+    FiveMinute,
+    TenMinute,
+    Hour,
+    Day,
+    Week,
+}
+
+impl Interval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::FifteenSecond => "15second",
+            Interval::FiveMinute => "5minute",
+            Interval::TenMinute => "10minute",
+            Interval::Hour => "hour",
+            Interval::Day => "day",
+            Interval::Week => "week",
+        }
+    }
+}
+
+/// How far back historical data points go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    Hour,
+    Day,
+    Week,
+    Month,
+    ThreeMonth,
+    Year,
+    FiveYear,
+}
+
+impl Span {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Span::Hour => "hour",
+            Span::Day => "day",
+            Span::Week => "week",
+            Span::Month => "month",
+            Span::ThreeMonth => "3month",
+            Span::Year => "year",
+            Span::FiveYear => "5year",
+        }
+    }
+}
+
+// "id": "1072fc76-1862-41ab-82c2-485837590762",
+// "code": "BTC",
+// "name": "Bitcoin",
+// "increment": "0.00000001",
+// "type": "cryptocurrency"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Currency {
+    pub id: String,
+    pub code: String,
+    pub name: String,
+    pub increment: String,
+    #[serde(rename = "type")]
+    pub currency_type: String,
+}
+
+// "id": "3d961844-d360-45fc-989b-f6fca761d511",
+// "symbol": "BTC-USD",
+// "name": "Bitcoin to US Dollar",
+// "tradability": "tradable",
+// "min_order_size": "0.000001000000000000",
+// "max_order_size": "20.0000000000",
+// "min_order_price_increment": "0.010000000000000000",
+// "min_order_quantity_increment": "0.000000010000000000",
+// "asset_currency": {...},
+// "quote_currency": {...}
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CurrencyPair {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub tradability: String,
+    pub min_order_size: String,
+    pub max_order_size: String,
+    pub min_order_price_increment: String,
+    pub min_order_quantity_increment: String,
+    pub asset_currency: Currency,
+    pub quote_currency: Currency,
+}
+
+// "ask_price": "50871.350000",
+// "bid_price": "50789.120000",
+// "mark_price": "50830.235000",
+// "high_price": "51722.430000",
+// "low_price": "49318.030000",
+// "open_price": "50119.620000",
+// "symbol": "BTCUSD",
+// "id": "3d961844-d360-45fc-989b-f6fca761d511",
+// "volume": "0.000000"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CryptoQuote {
+    pub ask_price: String,
+    pub bid_price: String,
+    pub mark_price: String,
+    pub high_price: String,
+    pub low_price: String,
+    pub open_price: String,
+    pub symbol: String,
+    pub id: String,
+    pub volume: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct HistoricalsResponse {
+    data_points: Vec<HistoricalDataPoint>,
+}
+
+// "begins_at": "2021-03-04T15:00:00Z",
+// "open_price": "50119.620000",
+// "close_price": "50512.110000",
+// "high_price": "50611.220000",
+// "low_price": "50002.840000",
+// "volume": 0,
+// "session": "reg",
+// "interpolated": false
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct HistoricalDataPoint {
+    pub begins_at: String,
+    pub open_price: String,
+    pub close_price: String,
+    pub high_price: String,
+    pub low_price: String,
+    pub volume: f64,
+    pub session: String,
+    pub interpolated: bool,
+}
@@ -0,0 +1,360 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::crypto::{CryptoQuote, CurrencyPair};
+use crate::error::RobinhoodErr;
+use crate::orders::{OrderState, OrderType, RefIdOrder, Side, TimeInForce};
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, CRYPTO_ORDERS_PATH, NUMMUS_API_URL};
+
+impl Robinhood {
+    /// Submits a crypto order to nummus.robinhood.com/orders/
+    ///
+    /// Market orders are sent with the ask (buy) or bid (sell) as price, the same way the app
+    /// does. Notional amounts are converted into a quantity at that price, rounded down to the
+    /// pair's quantity increment. Submissions are deduplicated on `ref_id` like `place_order`.
+    /// The kill switch, symbol lists and notional limits of the `RiskPolicy` apply.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{CryptoAmount, CryptoOrderRequest, Side};
+    ///
+    /// // Buy $250 worth of bitcoin
+    /// let request = CryptoOrderRequest::market("BTC", Side::Buy, CryptoAmount::Notional(250.0));
+    /// let order = robinhood_client.place_crypto_order(&request).await?;
+    /// ```
+    pub async fn place_crypto_order(
+        &self,
+        order: &CryptoOrderRequest,
+    ) -> Result<CryptoOrder, RobinhoodErr> {
+        if self.is_paper_trading() {
+            return Err(RobinhoodErr::InvalidOrder(
+                "Crypto orders are not supported while paper trading".to_owned(),
+            ));
+        }
+        let pair = self.get_currency_pair(&order.symbol).await?;
+        if pair.tradability != "tradable" {
+            return Err(RobinhoodErr::InvalidOrder(format!(
+                "{} is {}",
+                pair.symbol, pair.tradability
+            )));
+        }
+        let quote = self.get_crypto_quote_by_pair_id(&pair.id).await?;
+        let (price, quantity) = order.price_and_quantity(&pair, &quote)?;
+        let notional = price * quantity;
+        let reservation = self.check_basic_risk(&pair.asset_currency.code, notional)?;
+        let account = self.get_crypto_account().await?;
+        let payload = order.build_payload(&account.id, &pair, price, quantity);
+        let url = &format!("{}{}", NUMMUS_API_URL, CRYPTO_ORDERS_PATH);
+        reservation.settle(self.submit_with_ref_id(url, &payload, order.ref_id).await)
+    }
+
+    /// Calls nummus.robinhood.com/orders/ and returns every crypto order, newest first
+    pub async fn get_crypto_orders(&self) -> Result<Vec<CryptoOrder>, RobinhoodErr> {
+        let url = &format!("{}{}", NUMMUS_API_URL, CRYPTO_ORDERS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Calls nummus.robinhood.com/orders/(id)/ and returns the body as `CryptoOrder`
+    pub async fn get_crypto_order(&self, order_id: &str) -> Result<CryptoOrder, RobinhoodErr> {
+        let url = &format!("{}{}{}/", NUMMUS_API_URL, CRYPTO_ORDERS_PATH, order_id);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<CryptoOrder>(&response)
+    }
+
+    /// Requests the cancellation of an open crypto order
+    pub async fn cancel_crypto_order(&self, order_id: &str) -> Result<(), RobinhoodErr> {
+        if self.is_paper_trading() {
+            return Err(RobinhoodErr::InvalidOrder(
+                "Crypto orders are not supported while paper trading".to_owned(),
+            ));
+        }
+        let url = &format!(
+            "{}{}{}/cancel/",
+            NUMMUS_API_URL, CRYPTO_ORDERS_PATH, order_id
+        );
+        self.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            payload: None,
+            url,
+            timeout: None,
+            retry: false,
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// Size of a crypto order, either in coins or in USD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CryptoAmount {
+    Quantity(f64),
+    Notional(f64),
+}
+
+/// A crypto order to submit through `Robinhood::place_crypto_order`
+#[derive(Debug, Clone)]
+pub struct CryptoOrderRequest {
+    /// Asset code like "BTC", always traded against USD
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub amount: CryptoAmount,
+    /// Limit price, ignored for market orders
+    pub price: Option<f64>,
+    pub time_in_force: TimeInForce,
+    pub ref_id: Uuid,
+}
+
+impl CryptoOrderRequest {
+    /// A market order good till cancelled
+    pub fn market(symbol: &str, side: Side, amount: CryptoAmount) -> Self {
+        CryptoOrderRequest {
+            symbol: symbol.to_uppercase(),
+            side,
+            order_type: OrderType::Market,
+            amount,
+            price: None,
+            time_in_force: TimeInForce::Gtc,
+            ref_id: Uuid::new_v4(),
+        }
+    }
+
+    /// A limit order good till cancelled
+    pub fn limit(symbol: &str, side: Side, amount: CryptoAmount, price: f64) -> Self {
+        CryptoOrderRequest {
+            order_type: OrderType::Limit,
+            price: Some(price),
+            ..CryptoOrderRequest::market(symbol, side, amount)
+        }
+    }
+
+    // Price and quantity rounded to the increments of `pair`
+    fn price_and_quantity(
+        &self,
+        pair: &CurrencyPair,
+        quote: &CryptoQuote,
+    ) -> Result<(f64, f64), RobinhoodErr> {
+        let price = match (self.order_type, self.price) {
+            (OrderType::Limit, Some(price)) => price,
+            (OrderType::Limit, None) => {
+                return Err(RobinhoodErr::InvalidOrder(
+                    "Limit orders require a price".to_owned(),
+                ))
+            }
+            (OrderType::Market, _) => match self.side {
+                Side::Buy => quote.ask_price.parse::<f64>()?,
+                Side::Sell => quote.bid_price.parse::<f64>()?,
+            },
+        };
+        let price_increment = pair.min_order_price_increment.parse::<f64>()?;
+        let price = round_to_increment(price, price_increment);
+        let quantity = match self.amount {
+            CryptoAmount::Quantity(quantity) => quantity,
+            CryptoAmount::Notional(notional) => notional / price,
+        };
+        let quantity_increment = pair.min_order_quantity_increment.parse::<f64>()?;
+        let quantity = floor_to_increment(quantity, quantity_increment);
+        let min_size = pair.min_order_size.parse::<f64>()?;
+        if quantity < min_size || quantity <= 0.0 {
+            return Err(RobinhoodErr::InvalidOrder(format!(
+                "Quantity {} of {} is below the minimum order size {}",
+                quantity, pair.symbol, min_size
+            )));
+        }
+        Ok((price, quantity))
+    }
+
+    fn build_payload(
+        &self,
+        account_id: &str,
+        pair: &CurrencyPair,
+        price: f64,
+        quantity: f64,
+    ) -> Value {
+        json!({
+            "account_id": account_id,
+            "currency_pair_id": pair.id,
+            "side": self.side,
+            "type": self.order_type,
+            "time_in_force": self.time_in_force,
+            "price": format!("{:.*}", decimals(&pair.min_order_price_increment), price),
+            "quantity": format!("{:.*}", decimals(&pair.min_order_quantity_increment), quantity),
+            "ref_id": self.ref_id,
+        })
+    }
+}
+
+fn round_to_increment(value: f64, increment: f64) -> f64 {
+    if increment <= 0.0 {
+        return value;
+    }
+    (value / increment).round() * increment
+}
+
+fn floor_to_increment(value: f64, increment: f64) -> f64 {
+    if increment <= 0.0 {
+        return value;
+    }
+    // Nudge up before flooring so exact multiples don't lose an increment to float error
+    (value / increment + 1e-9).floor() * increment
+}
+
+// Decimal places of an increment like "0.000000010000000000"
+fn decimals(increment: &str) -> usize {
+    match increment.split_once('.') {
+        Some((_, fraction)) => fraction.trim_end_matches('0').len(),
+        None => 0,
+    }
+}
+
+// "id": "6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a",
+// "ref_id": "0a9d8c7b-6e5f-4a3b-2c1d-0e9f8a7b6c5d",
+// "account_id": "6a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d",
+// "currency_pair_id": "3d961844-d360-45fc-989b-f6fca761d511",
+// "cancel_url": null,
+// "side": "buy",
+// "type": "market",
+// "time_in_force": "gtc",
+// "state": "filled",
+// "price": "50871.35",
+// "quantity": "0.00491400",
+// "cumulative_quantity": "0.00491400",
+// "average_price": "50871.35",
+// "rounded_executed_notional": "249.98",
+// "executions": [{...}],
+// "created_at": "2021-03-04T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z",
+// "last_transaction_at": "2021-03-04T15:02:11.482381Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CryptoOrder {
+    pub id: String,
+    pub ref_id: Option<Uuid>,
+    pub account_id: String,
+    pub currency_pair_id: String,
+    pub cancel_url: Option<String>,
+    pub side: Side,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub state: OrderState,
+    pub price: String,
+    pub quantity: String,
+    pub cumulative_quantity: String,
+    pub average_price: Option<String>,
+    pub rounded_executed_notional: String,
+    pub executions: Vec<CryptoExecution>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_transaction_at: Option<String>,
+}
+
+impl RefIdOrder for CryptoOrder {
+    fn ref_id(&self) -> Option<Uuid> {
+        self.ref_id
+    }
+
+    fn created_at(&self) -> &str {
+        &self.created_at
+    }
+}
+
+// "id": "9b8a7c6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d",
+// "effective_price": "50871.350000000000000000",
+// "quantity": "0.004914000000000000",
+// "timestamp": "2021-03-04T15:02:11.593000Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CryptoExecution {
+    pub id: String,
+    pub effective_price: String,
+    pub quantity: String,
+    pub timestamp: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notional_is_converted_to_a_rounded_quantity() {
+        let pair = CurrencyPair {
+            symbol: "BTC-USD".to_owned(),
+            min_order_size: "0.000001000000000000".to_owned(),
+            min_order_price_increment: "0.010000000000000000".to_owned(),
+            min_order_quantity_increment: "0.000000010000000000".to_owned(),
+            ..Default::default()
+        };
+        let quote = CryptoQuote {
+            ask_price: "50000.004".to_owned(),
+            bid_price: "49990.000".to_owned(),
+            ..Default::default()
+        };
+        let order = CryptoOrderRequest::market("btc", Side::Buy, CryptoAmount::Notional(250.0));
+        let (price, quantity) = order.price_and_quantity(&pair, &quote).unwrap();
+        let payload = order.build_payload("account", &pair, price, quantity);
+        assert_eq!(payload["price"], "50000.00");
+        assert_eq!(payload["quantity"], "0.00500000");
+
+        let dust = CryptoOrderRequest::market("btc", Side::Sell, CryptoAmount::Quantity(1e-7));
+        assert!(dust.price_and_quantity(&pair, &quote).is_err());
+    }
+
+    #[test]
+    fn decodes_a_filled_order() {
+        let body = r#"{
+            "id": "6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a",
+            "ref_id": "0a9d8c7b-6e5f-4a3b-8c1d-0e9f8a7b6c5d",
+            "account_id": "6a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d",
+            "currency_pair_id": "3d961844-d360-45fc-989b-f6fca761d511",
+            "cancel_url": null,
+            "side": "buy",
+            "type": "market",
+            "time_in_force": "gtc",
+            "state": "filled",
+            "price": "50871.35",
+            "quantity": "0.00491400",
+            "cumulative_quantity": "0.00491400",
+            "average_price": "50871.35",
+            "rounded_executed_notional": "249.98",
+            "executions": [{
+                "id": "9b8a7c6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d",
+                "effective_price": "50871.350000000000000000",
+                "quantity": "0.004914000000000000",
+                "timestamp": "2021-03-04T15:02:11.593000Z"
+            }],
+            "created_at": "2021-03-04T15:02:11.482381Z",
+            "updated_at": "2021-03-04T15:02:11.719054Z",
+            "last_transaction_at": "2021-03-04T15:02:11.593000Z"
+        }"#;
+        let order: CryptoOrder = serde_json::from_str(body).unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(order.executions.len(), 1);
+        assert_eq!(
+            order.executions[0].effective_price,
+            "50871.350000000000000000"
+        );
+        assert_eq!(order.executions[0].quantity, "0.004914000000000000");
+    }
+
+    #[test]
+    fn paper_trading_never_cancels_live_orders() {
+        let mut client = futures::executor::block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        client.enable_paper_trading(10_000.0);
+        let cancelled = futures::executor::block_on(client.cancel_crypto_order("6a3e1a3e"));
+        assert!(matches!(cancelled, Err(RobinhoodErr::InvalidOrder(_))));
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Robinhood timestamps look like "2021-03-04T15:02:11.482381Z" and dates like "2021-03-08".
+// Only the few conversions the crate needs are implemented here, all in UTC.
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Days since 1970-01-01 of a civil date
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Civil date (year, month, day) of days since 1970-01-01
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of today in UTC
+pub(crate) fn today() -> i64 {
+    (now_secs() / 86400) as i64
+}
+
+/// Formats days since 1970-01-01 as "YYYY-MM-DD"
+pub(crate) fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The current time formatted the way Robinhood formats timestamps
+pub(crate) fn now_timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let time_of_day = secs % 86400;
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:06}Z",
+        format_date((secs / 86400) as i64),
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60,
+        now.subsec_micros()
+    )
+}
+
+/// Formats seconds since the epoch as "2021-03-04T15:02:11Z"
+pub(crate) fn format_timestamp(secs: i64) -> String {
+    let time_of_day = secs.rem_euclid(86400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_date(secs.div_euclid(86400)),
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+/// Seconds since the epoch of a "2021-03-04T15:02:11.482381Z" timestamp, fractions are dropped
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let field = |range: std::ops::Range<usize>| timestamp.get(range)?.parse::<i64>().ok();
+    let days = days_from_civil(field(0..4)?, field(5..7)? as u32, field(8..10)? as u32);
+    Some(days * 86400 + field(11..13)? * 3600 + field(14..16)? * 60 + field(17..19)?)
+}
+
+// 0 for Sunday
+fn weekday(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (days + 4).rem_euclid(7)
+}
+
+// Days of the nth Sunday of a month
+fn nth_sunday(year: i64, month: u32, n: i64) -> i64 {
+    let first = days_from_civil(year, month, 1);
+    first + (7 - weekday(first)) % 7 + (n - 1) * 7
+}
+
+/// The New York trading day (days since 1970-01-01) a UTC timestamp falls on
+///
+/// Daylight saving runs from 2:00 on the second Sunday of March
+/// to 2:00 on the first Sunday of November.
+pub(crate) fn eastern_day(secs: i64) -> i64 {
+    eastern_secs(secs).div_euclid(86400)
+}
+
+// New York local time of a UTC timestamp, as seconds since the epoch
+fn eastern_secs(secs: i64) -> i64 {
+    let (year, _, _) = civil_from_days(secs.div_euclid(86400));
+    let dst_start = nth_sunday(year, 3, 2) * 86400 + 7 * 3600;
+    let dst_end = nth_sunday(year, 11, 1) * 86400 + 6 * 3600;
+    let offset = if secs >= dst_start && secs < dst_end {
+        4 * 3600
+    } else {
+        5 * 3600
+    };
+    secs - offset
+}
+
+/// `true` from 4:00 to 20:00 New York time on weekdays, when quotes move including
+/// extended hours. Market holidays are not taken into account
+pub(crate) fn is_trading_hours(secs: i64) -> bool {
+    let local = eastern_secs(secs);
+    let time_of_day = local.rem_euclid(86400);
+    is_weekday(local.div_euclid(86400)) && (4 * 3600..20 * 3600).contains(&time_of_day)
+}
+
+/// `is_trading_hours` for the current time
+pub(crate) fn is_trading_hours_now() -> bool {
+    is_trading_hours(now_secs() as i64)
+}
+
+/// Today's New York trading day
+pub(crate) fn eastern_today() -> i64 {
+    eastern_day(now_secs() as i64)
+}
+
+/// `true` for Monday to Friday
+pub(crate) fn is_weekday(days: i64) -> bool {
+    (1..=5).contains(&weekday(days))
+}
+
+/// Moves `count` weekdays forward from `days`
+pub(crate) fn add_weekdays(mut days: i64, mut count: u32) -> i64 {
+    while count > 0 {
+        days += 1;
+        if is_weekday(days) {
+            count -= 1;
+        }
+    }
+    days
+}
+
+/// Moves `count` weekdays back from `days`
+pub(crate) fn sub_weekdays(mut days: i64, mut count: u32) -> i64 {
+    while count > 0 {
+        days -= 1;
+        if is_weekday(days) {
+            count -= 1;
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(days_from_civil(2021, 3, 4), 18690);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(18690), (2021, 3, 4));
+        assert_eq!(format_date(18321), "2020-02-29");
+    }
+
+    #[test]
+    fn weekdays() {
+        // 2021-03-05 is a Friday
+        let friday = 18691;
+        assert!(is_weekday(friday));
+        assert!(!is_weekday(friday + 1));
+        assert_eq!(format_date(add_weekdays(friday, 2)), "2021-03-09");
+        assert_eq!(format_date(sub_weekdays(friday + 3, 4)), "2021-03-02");
+    }
+
+    #[test]
+    fn eastern_trading_days() {
+        // 23:30 UTC is still the same day in New York, in winter and in summer
+        let winter = parse_timestamp("2021-03-04T23:30:00.000000Z").unwrap();
+        assert_eq!(format_date(eastern_day(winter)), "2021-03-04");
+        let summer = parse_timestamp("2021-07-01T03:30:00Z").unwrap();
+        assert_eq!(format_date(eastern_day(summer)), "2021-06-30");
+        let summer = parse_timestamp("2021-07-01T04:30:00Z").unwrap();
+        assert_eq!(format_date(eastern_day(summer)), "2021-07-01");
+        assert!(parse_timestamp("not a timestamp").is_none());
+    }
+
+    #[test]
+    fn trading_hours() {
+        let at = |timestamp: &str| is_trading_hours(parse_timestamp(timestamp).unwrap());
+        // Thursday 8:59 and 9:00 in New York, winter time
+        assert!(!at("2021-03-04T08:59:00Z"));
+        assert!(at("2021-03-04T09:00:00Z"));
+        // Thursday 19:59 and 20:00 in New York, summer time
+        assert!(at("2021-07-01T23:59:00Z"));
+        assert!(!at("2021-07-02T00:00:00Z"));
+        // Saturday noon
+        assert!(!at("2021-03-06T17:00:00Z"));
+    }
+}
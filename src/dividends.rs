@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::{Robinhood, DIVIDENDS_PATH, ROBINHOOD_API_URL};
+
+impl Robinhood {
+    /// Calls api.robinhood.com/dividends/ and returns every dividend, paid or upcoming,
+    /// with `symbol` resolved from its instrument
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{dividends_by_symbol, dividends_by_year};
+    ///
+    /// let dividends = robinhood_client.get_dividends().await?;
+    /// for (year, total) in dividends_by_year(&dividends)? {
+    ///     println!("{}: ${:.2}", year, total);
+    /// }
+    /// let upcoming: Vec<_> = dividends.iter().filter(|d| d.state == "pending").collect();
+    /// ```
+    pub async fn get_dividends(&self) -> Result<Vec<Dividend>, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, DIVIDENDS_PATH);
+        let mut dividends: Vec<Dividend> = self.paginate(url).collect_all(None).await?;
+        let mut ids: Vec<&str> = dividends
+            .iter()
+            .map(|dividend| instrument_id(&dividend.instrument))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let instruments = self.get_instruments(&ids).await?;
+        for dividend in &mut dividends {
+            dividend.symbol = instruments
+                .iter()
+                .find(|instrument| instrument.url == dividend.instrument)
+                .map(|instrument| instrument.symbol.clone());
+        }
+        Ok(dividends)
+    }
+}
+
+// The id at the end of an instrument URL
+fn instrument_id(url: &str) -> &str {
+    url.trim_end_matches('/').rsplit('/').next().unwrap_or(url)
+}
+
+/// Sums the `amount` of the paid dividends by the year of their `payable_date`
+pub fn dividends_by_year(dividends: &[Dividend]) -> Result<BTreeMap<i32, f64>, RobinhoodErr> {
+    let mut totals = BTreeMap::new();
+    for dividend in dividends.iter().filter(|dividend| dividend.is_paid()) {
+        let year = dividend
+            .payable_date
+            .get(..4)
+            .and_then(|year| year.parse::<i32>().ok())
+            .ok_or_else(|| RobinhoodErr::BadResponseBody(dividend.payable_date.clone()))?;
+        *totals.entry(year).or_insert(0.0) += dividend.amount.parse::<f64>()?;
+    }
+    Ok(totals)
+}
+
+/// Sums the `amount` of the paid dividends by symbol, unresolved symbols are left out
+pub fn dividends_by_symbol(dividends: &[Dividend]) -> Result<BTreeMap<String, f64>, RobinhoodErr> {
+    let mut totals = BTreeMap::new();
+    for dividend in dividends.iter().filter(|dividend| dividend.is_paid()) {
+        if let Some(symbol) = &dividend.symbol {
+            *totals.entry(symbol.clone()).or_insert(0.0) += dividend.amount.parse::<f64>()?;
+        }
+    }
+    Ok(totals)
+}
+
+// "id": "2a7b9c0d-4e5f-4a1b-8c2d-3e4f5a6b7c8d",
+// "url": "https://api.robinhood.com/dividends/2a7b9c0d-4e5f-4a1b-8c2d-3e4f5a6b7c8d/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "instrument": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "amount": "14.78",
+// "rate": "1.4780000000",
+// "position": "10.00000000",
+// "withholding": "0.00",
+// "record_date": "2021-03-22",
+// "payable_date": "2021-04-30",
+// "paid_at": "2021-04-30T17:32:11.482381Z",
+// "state": "paid"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Dividend {
+    pub id: String,
+    pub url: String,
+    pub account: String,
+    pub instrument: String,
+    /// Gross amount, before `withholding`
+    pub amount: String,
+    /// Dollars per share
+    pub rate: String,
+    /// Shares held on `record_date`
+    pub position: String,
+    pub withholding: String,
+    pub record_date: String,
+    pub payable_date: String,
+    /// `None` until it is paid
+    pub paid_at: Option<String>,
+    /// "pending" for upcoming payouts, then "paid" or "reinvested", or "voided"
+    pub state: String,
+    /// Symbol of `instrument`, filled in by `get_dividends`
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+impl Dividend {
+    /// Paid out in cash or reinvested
+    pub fn is_paid(&self) -> bool {
+        self.state == "paid" || self.state == "reinvested"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dividend(symbol: &str, amount: &str, payable_date: &str, state: &str) -> Dividend {
+        Dividend {
+            amount: amount.to_owned(),
+            payable_date: payable_date.to_owned(),
+            state: state.to_owned(),
+            symbol: Some(symbol.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sums_paid_dividends() {
+        let dividends = vec![
+            dividend("SPY", "1.50", "2020-12-31", "paid"),
+            dividend("SPY", "2.00", "2021-03-31", "reinvested"),
+            dividend("AAPL", "0.25", "2021-05-13", "paid"),
+            dividend("AAPL", "0.25", "2021-08-12", "pending"),
+            dividend("AAPL", "9.99", "2021-02-11", "voided"),
+        ];
+        let by_year = dividends_by_year(&dividends).unwrap();
+        assert_eq!(
+            by_year.into_iter().collect::<Vec<_>>(),
+            vec![(2020, 1.5), (2021, 2.25)]
+        );
+        let by_symbol = dividends_by_symbol(&dividends).unwrap();
+        assert_eq!(by_symbol["SPY"], 3.5);
+        assert_eq!(by_symbol["AAPL"], 0.25);
+        assert_eq!(
+            instrument_id("https://api.robinhood.com/instruments/8f92e76f/"),
+            "8f92e76f"
+        );
+    }
+}
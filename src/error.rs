@@ -1,23 +1,67 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use std::num::ParseFloatError;
+use std::time::Duration;
 
-use crate::ReqwestError;
+use serde_json::Value;
+
+use crate::RiskRule;
 use thiserror::Error;
 
+// Longest part of a response body kept in errors
+const BODY_SNIPPET_LEN: usize = 512;
+
 #[derive(Error, Debug)]
 pub enum RobinhoodErr {
-    #[error("Unauthorized request 401")]
-    Unauthorized,
+    /// 401, the token expired or was revoked
+    #[error("Unauthorized request 401: {0}")]
+    Unauthorized(Box<ApiError>),
     #[error("404 NOT FOUND URL: {0}")]
     NotFound(String),
-    #[error("{0}")]
-    NetworkError(String),
-    #[error("{0}")]
-    RequestError(#[from] ReqwestError),
+    /// 400, Robinhood rejected the request, see `ApiError::field_errors`
+    #[error("Validation failed: {0}")]
+    Validation(Box<ApiError>),
+    /// 403, the account is not allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(Box<ApiError>),
+    /// 429, still throttled after every retry of the `RetryPolicy`
+    #[error("Rate limited by Robinhood, retry after {retry_after:?}: {error}")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        error: Box<ApiError>,
+    },
+    /// 5xx, still failing after every retry of the `RetryPolicy`
+    #[error("Robinhood server error: {0}")]
+    Server(Box<ApiError>),
+    /// Any other unexpected status
+    #[error("Unexpected response: {0}")]
+    Api(Box<ApiError>),
+    /// The response body didn't have the expected shape
+    #[error("Failed to decode the {status} response of {endpoint} ({message}): {body}")]
+    Decode {
+        endpoint: String,
+        status: u16,
+        message: String,
+        /// The beginning of the raw body
+        body: String,
+    },
+    #[error("Request to {endpoint} timed out")]
+    Timeout { endpoint: String },
+    /// The request never got a response (DNS, connection, TLS...)
+    #[error("Request to {endpoint} failed: {source}")]
+    Network {
+        endpoint: String,
+        /// The error of the `HttpTransport`
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A custom `HttpTransport` failed to send the request
+    #[error("Transport failed for {endpoint}: {message}")]
+    Transport { endpoint: String, message: String },
     /// Invalid log in credentials
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// use robinhood::RobinhoodErr;
     /// let price = robinhood.get_price("SPY").await {
     ///     Ok(price) => price,
@@ -37,7 +81,7 @@ pub enum RobinhoodErr {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// use robinhood::RobinhoodErr;
     /// let mut robinhood_client = robinhood::mfa_login(username, password).await {
     ///     Ok(client) => client,
@@ -53,52 +97,211 @@ pub enum RobinhoodErr {
     /// ```
     #[error("Invalid username/password")]
     InvalidCredentials,
-    #[error("{0}")]
-    BadResponseBody(String),
-    #[error("The refresh token '{0}' is no longer valid")]
-    BadRefreshToken(String),
-}
-
-#[derive(Error, Debug)]
-pub enum LoginErr {
-    #[error("{0}")]
-    RequestError(#[from] ReqwestError),
     #[error("Failed to serialize login payload ({0})")]
     BadLoginBody(String),
     #[error("Log in payload is empty. This should never happen. Something went terrible wrong")]
     EmptyLoginBody,
     #[error("Mfa code was not added to the request body correctly")]
     MissingMfaCode,
-    /// Invalid log in credentials
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use robinhood::LoginErr;
-    /// let mut robinhood_client = mfa_client.log_in(mfa_code).await {
-    ///     Ok(client) => client,
-    ///     Err(e) => {
-    ///         match e {
-    ///            LoginErr::InvalidCredentials => {
-    ///                 panic!("wrong username/password")
-    ///            },
-    ///            _ => {panic!(e)}
-    ///         }
-    ///     }
-    /// };
-    /// ```
-    #[error("Invalid username/password")]
-    InvalidCredentials,
     #[error("{0}")]
     BadResponseBody(String),
+    /// Robinhood refused to refresh the token, log in again to get a new one
+    #[error("The refresh token is no longer valid")]
+    BadRefreshToken,
+    /// A `Middleware` aborted the request
+    #[error("Aborted by middleware: {0}")]
+    Middleware(String),
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    /// The transfer was refused by `Robinhood::prepare_transfer` and never sent
+    #[error("Invalid transfer: {0}")]
+    InvalidTransfer(String),
+    /// The order violated the client's `RiskPolicy` and was never sent
+    #[error("Order rejected by risk rule '{rule}': {detail}")]
+    RiskRejected { rule: RiskRule, detail: String },
 }
 
-#[derive(Error, Debug)]
-pub enum RefreshTokenErr {
-    #[error("The refresh token '{0}' is no longer valid")]
-    BadRefreshToken(String),
-    #[error("{0}")]
-    RequestError(#[from] ReqwestError),
-    #[error("{0}")]
-    WrongResponseBody(String),
+/// Errors of `MfaLogin::log_in`, the same type as every other error of the crate
+pub type LoginErr = RobinhoodErr;
+/// Errors of `Robinhood::refresh_token`, the same type as every other error of the crate
+pub type RefreshTokenErr = RobinhoodErr;
+
+impl RobinhoodErr {
+    /// HTTP status of the response behind the error, if there was one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            RobinhoodErr::NotFound(_) => Some(404),
+            RobinhoodErr::Decode { status, .. } => Some(*status),
+            _ => self.api_error().map(|error| error.status),
+        }
+    }
+
+    /// Details of the error response returned by Robinhood
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            RobinhoodErr::Unauthorized(error)
+            | RobinhoodErr::Validation(error)
+            | RobinhoodErr::Forbidden(error)
+            | RobinhoodErr::RateLimited { error, .. }
+            | RobinhoodErr::Server(error)
+            | RobinhoodErr::Api(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// `true` when the request may have reached Robinhood but its outcome is unknown
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(
+            self,
+            RobinhoodErr::Server(_) | RobinhoodErr::Timeout { .. } | RobinhoodErr::Network { .. }
+        )
+    }
+}
+
+/// An error response of the Robinhood API
+///
+/// ```text
+/// {"detail": "Not enough shares to sell."}
+/// {"non_field_errors": ["Order quantity has invalid increment."]}
+/// {"price": ["Ensure that there are no more than 2 decimal places."]}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    /// URL of the request without its query string
+    pub endpoint: String,
+    /// The `x-request-id` header, to quote when contacting Robinhood
+    pub request_id: Option<String>,
+    pub detail: Option<String>,
+    pub non_field_errors: Vec<String>,
+    /// Messages per request field
+    pub field_errors: BTreeMap<String, Vec<String>>,
+    /// The beginning of the raw body
+    pub body: String,
+}
+
+impl ApiError {
+    pub(crate) fn from_body(
+        status: u16,
+        endpoint: &str,
+        request_id: Option<String>,
+        body: &str,
+    ) -> Self {
+        let mut error = ApiError {
+            status,
+            endpoint: strip_query(endpoint).to_owned(),
+            request_id,
+            body: snippet(body),
+            ..ApiError::default()
+        };
+        let fields = match serde_json::from_str::<Value>(body) {
+            Ok(Value::Object(fields)) => fields,
+            _ => return error,
+        };
+        for (field, value) in fields {
+            let messages = messages(&value);
+            match field.as_str() {
+                // OAuth errors of the login endpoint
+                "detail" | "error_description" => error.detail = messages.into_iter().next(),
+                "error" => {}
+                "non_field_errors" => error.non_field_errors = messages,
+                _ if !messages.is_empty() => {
+                    error.field_errors.insert(field, messages);
+                }
+                _ => {}
+            }
+        }
+        error
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.status, self.endpoint)?;
+        let mut messages = Vec::new();
+        messages.extend(self.detail.iter().cloned());
+        messages.extend(self.non_field_errors.iter().cloned());
+        for (field, errors) in &self.field_errors {
+            messages.push(format!("{}: {}", field, errors.join(" ")));
+        }
+        if messages.is_empty() && !self.body.is_empty() {
+            messages.push(self.body.clone());
+        }
+        if !messages.is_empty() {
+            write!(f, " {}", messages.join("; "))?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, " (request id {})", request_id)?;
+        }
+        Ok(())
+    }
+}
+
+// Strings of an error value, a single message or a list of them
+fn messages(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(message) => vec![message.clone()],
+        Value::Array(values) => values.iter().flat_map(messages).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn strip_query(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+pub(crate) fn snippet(body: &str) -> String {
+    match body.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_owned(),
+    }
+}
+
+/// The error matching the status of an unsuccessful response
+pub(crate) fn status_error(error: ApiError, retry_after: Option<Duration>) -> RobinhoodErr {
+    let status = error.status;
+    let error = Box::new(error);
+    match status {
+        400 => RobinhoodErr::Validation(error),
+        401 => RobinhoodErr::Unauthorized(error),
+        403 => RobinhoodErr::Forbidden(error),
+        404 => RobinhoodErr::NotFound(error.endpoint),
+        429 => RobinhoodErr::RateLimited { retry_after, error },
+        500..=599 => RobinhoodErr::Server(error),
+        _ => RobinhoodErr::Api(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_api_error_bodies() {
+        let body = r#"{"non_field_errors": ["Order quantity has invalid increment."],
+            "price": ["Ensure that there are no more than 2 decimal places."]}"#;
+        let error = ApiError::from_body(
+            400,
+            "https://api.robinhood.com/orders/?a=b",
+            Some("abc".to_owned()),
+            body,
+        );
+        assert_eq!(error.endpoint, "https://api.robinhood.com/orders/");
+        assert_eq!(error.non_field_errors.len(), 1);
+        assert_eq!(error.field_errors["price"].len(), 1);
+        assert_eq!(
+            error.to_string(),
+            "400 https://api.robinhood.com/orders/ Order quantity has invalid increment.; \
+             price: Ensure that there are no more than 2 decimal places. (request id abc)"
+        );
+
+        let error = ApiError::from_body(403, "url", None, r#"{"detail": "Forbidden."}"#);
+        assert_eq!(error.detail.as_deref(), Some("Forbidden."));
+        let error = ApiError::from_body(502, "url", None, "<html>Bad Gateway</html>");
+        assert_eq!(error.to_string(), "502 url <html>Bad Gateway</html>");
+        assert_eq!(
+            snippet(&"é".repeat(600)).chars().count(),
+            BODY_SNIPPET_LEN + 3
+        );
+    }
 }
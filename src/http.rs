@@ -0,0 +1,259 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{strip_query, RobinhoodErr};
+use crate::middleware::Middleware;
+use crate::req::ReqKind;
+use crate::Robinhood;
+
+/// An outgoing request as seen by middlewares
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: ReqKind,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// JSON payload of POSTs
+    pub body: Option<Value>,
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequest {
+    /// First value of a header, names are case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Sets a header, replacing any previous value
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_owned(), value.to_owned()));
+    }
+}
+
+/// A response with its body fully read, as seen by middlewares
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// Final URL, after redirects
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// First value of a header, names are case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Sends requests once every middleware ran, `ReqwestTransport` by default
+///
+/// Any status is a successful send, errors are for requests that got no response.
+/// `RobinhoodErr::Timeout` and `RobinhoodErr::Network` are retried according to the
+/// `RetryPolicy` of the session, `RobinhoodErr::Transport` never is.
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::future::{self, BoxFuture, FutureExt};
+/// use robinhood::{HttpRequest, HttpResponse, HttpTransport, RobinhoodErr};
+///
+/// // Answers every request with an empty object
+/// struct Fake;
+///
+/// impl HttpTransport for Fake {
+///     fn send<'a>(
+///         &'a self,
+///         request: &'a HttpRequest,
+///     ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+///         let response = HttpResponse {
+///             status: 200,
+///             url: request.url.clone(),
+///             headers: Vec::new(),
+///             body: "{}".to_owned(),
+///         };
+///         future::ready(Ok(response)).boxed()
+///     }
+/// }
+///
+/// robinhood_client.set_transport(Arc::new(Fake));
+/// ```
+pub trait HttpTransport: Send + Sync {
+    fn send<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+    ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>>;
+}
+
+/// Sends requests over the network with `reqwest`
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Self {
+        ReqwestTransport::default()
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+    ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+        send(&self.client, request).boxed()
+    }
+}
+
+/// Runs `request` through the before hooks of `middlewares`, sends it with `transport`
+/// and runs the after hooks in reverse order
+pub(crate) async fn execute(
+    middlewares: &[Arc<dyn Middleware>],
+    transport: &dyn HttpTransport,
+    mut request: HttpRequest,
+) -> Result<HttpResponse, RobinhoodErr> {
+    for middleware in middlewares {
+        middleware.before_request(&mut request).await?;
+    }
+    let mut response = transport.send(&request).await?;
+    for middleware in middlewares.iter().rev() {
+        middleware.after_response(&request, &mut response).await?;
+    }
+    Ok(response)
+}
+
+async fn send(
+    client: &reqwest::Client,
+    request: &HttpRequest,
+) -> Result<HttpResponse, RobinhoodErr> {
+    let mut builder = match request.method {
+        ReqKind::Get => client.get(&request.url),
+        ReqKind::Post => client.post(&request.url),
+        ReqKind::Delete => client.delete(&request.url),
+    };
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = &request.body {
+        builder = builder.json(body);
+    }
+    if let Some(timeout) = request.timeout {
+        builder = builder.timeout(timeout);
+    }
+    let res = builder
+        .send()
+        .await
+        .map_err(|e| transport_error(&request.url, e))?;
+    let status = res.status().as_u16();
+    let url = res.url().to_string();
+    let headers = res
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+        })
+        .collect();
+    let body = res
+        .text()
+        .await
+        .map_err(|e| transport_error(&request.url, e))?;
+    Ok(HttpResponse {
+        status,
+        url,
+        headers,
+        body,
+    })
+}
+
+// Maps a failure to get a response from `url`. Errors building the request are not
+// worth retrying
+fn transport_error(url: &str, e: reqwest::Error) -> RobinhoodErr {
+    let endpoint = strip_query(url).to_owned();
+    if e.is_timeout() {
+        RobinhoodErr::Timeout { endpoint }
+    } else if e.is_builder() {
+        RobinhoodErr::Transport {
+            endpoint,
+            message: e.to_string(),
+        }
+    } else {
+        RobinhoodErr::Network {
+            endpoint,
+            source: Box::new(e),
+        }
+    }
+}
+
+impl Robinhood {
+    /// Replaces the transport sending the requests of the session, see `HttpTransport`
+    pub fn set_transport(&mut self, transport: Arc<dyn HttpTransport>) {
+        self.transport = transport;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    use crate::RateLimits;
+
+    // Keeps the requests and answers them all with a 404
+    #[derive(Default)]
+    struct Fake {
+        sent: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl HttpTransport for Fake {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            self.sent.lock().unwrap().push(request.clone());
+            let response = HttpResponse {
+                status: 404,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: String::new(),
+            };
+            futures::future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn requests_go_through_the_transport() {
+        let device_token = uuid::Uuid::new_v4();
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            device_token,
+        ));
+        let fake = Arc::new(Fake::default());
+        client.set_transport(fake.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let res = block_on(client.get_quote("SPY"));
+        assert!(matches!(res, Err(RobinhoodErr::NotFound(_))));
+        let sent = fake.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].method, ReqKind::Get);
+        assert_eq!(sent[0].header("authorization"), Some("Bearer token"));
+    }
+}
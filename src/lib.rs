@@ -4,7 +4,7 @@
 //!
 //! # Example
 //!
-//! ```
+//! ```ignore
 //! use robinhood;
 //!
 //! #[tokio::main]
@@ -19,39 +19,119 @@
 //!     // Needs to be `mut` will revise this in the future
 //!     let mut robinhood_client = mfa_client.log_in(mfa_code).await?;
 //!
-//!     // Get the price of SPY in an interval
+//!     // Follow the price of SPY, a quote is emitted every time it changes
+//!     // Requests are paced by the client, see `RateLimits` to change the budgets
+//!     use futures::StreamExt;
 //!     use std::time::Duration;
-//!     use std::thread;
 //!
-//!     loop {
-//!         // Use some timer to not spam Robinhood with requests.. you might get banned
-//!         thread::sleep(Duration::from_millis(500));
-//!         let price: usize = robinhood_client.get_price("SPY").await?;
-//!         println!("{}", price);
+//!     let mut quotes = robinhood_client.quote_stream(&["SPY"], Duration::from_secs(1));
+//!     while let Some(quote) = quotes.next().await {
+//!         println!("{}", quote?.last_trade_price);
 //!     }
 //!
 //! }
 //! ```
-pub use reqwest::Error as ReqwestError;
-
-pub use error::{LoginErr, RobinhoodErr};
-use login::MfaLogin;
+pub use alerts::{AlertEvent, AlertRule, AlertSink, Alerts};
+pub use cassette::{RecordingTransport, ReplayTransport};
+pub use crypto::{
+    CostBasis, CryptoAccount, CryptoAmount, CryptoExecution, CryptoHolding, CryptoOrder,
+    CryptoOrderRequest, CryptoQuote, Currency, CurrencyPair, HistoricalDataPoint, Interval, Span,
+};
+pub use dividends::{dividends_by_symbol, dividends_by_year, Dividend};
+pub use error::{ApiError, LoginErr, RobinhoodErr};
+pub use http::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+pub use login::MfaLogin;
+pub use metrics::{MetricsSink, PrometheusSink};
+pub use middleware::{HeaderMiddleware, LoggingMiddleware, MetricsMiddleware, Middleware};
+pub use options::{
+    AggregateOptionLeg, AggregateOptionPosition, Direction, Greeks, MinTicks, OptionChain,
+    OptionExposure, OptionInstrument, OptionLeg, OptionMarketData, OptionOrder, OptionOrderLeg,
+    OptionOrderRequest, OptionPosition, OptionPositionType, OptionType, PositionEffect,
+    UnderlyingInstrument, ValuedOptionPosition,
+};
+pub use orders::{
+    Execution, Order, OrderRequest, OrderState, OrderType, Side, TimeInForce, Trigger,
+};
+pub use paginated::Paginated;
+use paper::PaperBroker;
+pub use paper::{PaperAccount, PaperPosition};
+pub use pdt::{DayTradeSummary, DAY_TRADE_LIMIT};
+pub use queries::{Account, Instrument, Position, QuotesResponse};
+use quote_stream::QuoteHub;
+pub use quote_stream::QuoteStream;
+use rate_limit::RateLimiter;
+pub use rate_limit::{EndpointGroup, RateLimit, RateLimits};
+pub use req::{ReqKind, RetryPolicy};
+use risk::RiskGuard;
+pub use risk::{RiskPolicy, RiskRule};
+use std::sync::Arc;
+pub use transfers::{
+    AchRelationship, Transfer, TransferConfirmation, TransferDirection, TransferRequest,
+};
 use uuid::Uuid;
+pub use watchlists::{Watchlist, WatchlistItem};
 
-// Base URL
+// Base URLs
 const ROBINHOOD_API_URL: &str = "https://api.robinhood.com/";
+const NUMMUS_API_URL: &str = "https://nummus.robinhood.com/";
 // Paths
 const LOG_IN_PATH: &str = "oauth2/token/";
 const QUOTES_PATH: &str = "quotes/";
+const QUOTES_HISTORICALS_PATH: &str = "quotes/historicals/";
+const ACCOUNTS_PATH: &str = "accounts/";
+const ORDERS_PATH: &str = "orders/";
+const POSITIONS_PATH: &str = "positions/";
+const OPTIONS_CHAINS_PATH: &str = "options/chains/";
+const OPTIONS_INSTRUMENTS_PATH: &str = "options/instruments/";
+const OPTIONS_MARKET_DATA_PATH: &str = "marketdata/options/";
+const OPTIONS_ORDERS_PATH: &str = "options/orders/";
+const OPTIONS_POSITIONS_PATH: &str = "options/positions/";
+const OPTIONS_AGGREGATE_POSITIONS_PATH: &str = "options/aggregate_positions/";
+const CRYPTO_QUOTES_PATH: &str = "marketdata/forex/quotes/";
+const CRYPTO_HISTORICALS_PATH: &str = "marketdata/forex/historicals/";
+const INSTRUMENTS_PATH: &str = "instruments/";
+const DIVIDENDS_PATH: &str = "dividends/";
+const ACH_RELATIONSHIPS_PATH: &str = "ach/relationships/";
+const ACH_TRANSFERS_PATH: &str = "ach/transfers/";
+const WATCHLISTS_PATH: &str = "midlands/lists/";
+const WATCHLIST_ITEMS_PATH: &str = "midlands/lists/items/";
+// Paths on nummus.robinhood.com
+const CRYPTO_PAIRS_PATH: &str = "currency_pairs/";
+const CRYPTO_ACCOUNTS_PATH: &str = "accounts/";
+const CRYPTO_HOLDINGS_PATH: &str = "holdings/";
+const CRYPTO_ORDERS_PATH: &str = "orders/";
 
 const CLIENT_ID: &str = "c82SH0WZOsabOXGP2sxqcj34FxkvfnWRZBKlBjFS";
 const EXPIRES_IN: u32 = 86400;
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/88.0.4324.182 Safari/537.36 Edg/88.0.705.81";
 
+#[macro_use]
+mod trace;
+
+mod alerts;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cassette;
+mod crypto;
+mod datetime;
+mod dividends;
 pub mod error;
+mod http;
 mod login;
+pub mod metrics;
+mod middleware;
+mod options;
+mod orders;
+mod paginated;
+mod paper;
+mod pdt;
 mod queries;
+mod quote_stream;
+mod rate_limit;
 mod req;
+mod risk;
+mod transfers;
+mod watchlists;
 
 /// A Robinhood client instance
 pub struct Robinhood {
@@ -63,12 +143,20 @@ pub struct Robinhood {
     device_token: Uuid,
     user_agent: String,
     auto_refresh: bool,
+    risk: RiskGuard,
+    paper: Option<PaperBroker>,
+    limiter: RateLimiter,
+    retry: RetryPolicy,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    transport: Arc<dyn HttpTransport>,
+    quotes: QuoteHub,
 }
 /// Initializes an MFA login session
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use robinhood;
 ///
 /// #[tokio::main]
@@ -83,15 +171,13 @@ pub struct Robinhood {
 ///     // Needs to be `mut` will revise this in the future
 ///     let mut robinhood_client = mfa_client.log_in(mfa_code).await?;
 ///
-///     // Get the price of SPY in an interval
+///     // Follow the price of SPY, a quote is emitted every time it changes
+///     use futures::StreamExt;
 ///     use std::time::Duration;
-///     use std::thread;
 ///
-///     loop {
-///         // Use some timer to not spam Robinhood with requests.. you might get banned
-///         thread::sleep(Duration::from_millis(500));
-///         let price: usize = robinhood_client.get_price("SPY").await?;
-///         println!("{}", price);
+///     let mut quotes = robinhood_client.quote_stream(&["SPY"], Duration::from_secs(1));
+///     while let Some(quote) = quotes.next().await {
+///         println!("{}", quote?.last_trade_price);
 ///     }
 ///
 /// }
@@ -105,7 +191,7 @@ pub async fn mfa_login(username: String, password: String) -> Result<MfaLogin, R
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use robinhood;
 /// use uuid;
 ///
@@ -120,15 +206,13 @@ pub async fn mfa_login(username: String, password: String) -> Result<MfaLogin, R
 ///     // If you have a valid refresh token
 ///     // and `Robinhood.auto_refresh` is set to true then it will create a new one.
 ///
-///     // Get the price of SPY in an interval
+///     // Follow the price of SPY, a quote is emitted every time it changes
+///     use futures::StreamExt;
 ///     use std::time::Duration;
-///     use std::thread;
 ///
-///     loop {
-///         // Use some timer to not spam Robinhood with requests.. you might get banned
-///         thread::sleep(Duration::from_millis(500));
-///         let price: usize = robinhood_client.get_price("SPY").await?;
-///         println!("{}", price);
+///     let mut quotes = robinhood_client.quote_stream(&["SPY"], Duration::from_secs(1));
+///     while let Some(quote) = quotes.next().await {
+///         println!("{}", quote?.last_trade_price);
 ///     }
 ///
 /// }
@@ -1,11 +1,23 @@
+use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::{ParseError, Uuid};
 
-use crate::{error::RefreshTokenErr, req::set_req_headers, LoginErr, RobinhoodErr};
-use crate::{Robinhood, CLIENT_ID, EXPIRES_IN, LOG_IN_PATH, ROBINHOOD_API_URL, USER_AGENT};
+use crate::error::RefreshTokenErr;
+use crate::http::{execute, HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+use crate::metrics::{RATE_LIMITER_WAIT_SECONDS, TOKEN_REFRESHES_TOTAL};
+use crate::middleware::Middleware;
+use crate::quote_stream::QuoteHub;
+use crate::req::{check_status, decode, req_headers, ReqKind};
+use crate::trace::REDACTED;
+use crate::{
+    EndpointGroup, RateLimiter, RateLimits, RetryPolicy, RiskGuard, Robinhood, CLIENT_ID,
+    EXPIRES_IN, LOG_IN_PATH, ROBINHOOD_API_URL, USER_AGENT,
+};
+use crate::{LoginErr, RobinhoodErr};
 pub trait AgentToken {
     fn get_user_agent(&self) -> &str;
     fn get_token(&self) -> Option<&str>;
@@ -19,7 +31,7 @@ pub trait AgentToken {
 // username: "<>",
 // password: "<>"
 // mfa_code: "111111"
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct LogInPayload {
     client_id: String,
     device_token: Uuid,
@@ -36,7 +48,7 @@ pub struct LogInPayload {
 // "grant_type": "refresh_token",
 // "client_id": "<>",
 // "device_token": "<>"
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct RefreshTokenPayload {
     token_type: TokenType,
     scope: Scope,
@@ -53,7 +65,7 @@ pub struct RefreshTokenPayload {
 // refresh_token: "<>",
 // mfa_code: "329503",
 // backup_code: null
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct LoginSuccess {
     access_token: String,
     expires_in: u32,
@@ -64,6 +76,48 @@ pub struct LoginSuccess {
     backup_code: Option<Value>,
 }
 
+// Secrets never show up in logs, whatever `Debug` is used for
+impl fmt::Debug for LogInPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogInPayload")
+            .field("client_id", &self.client_id)
+            .field("device_token", &self.device_token)
+            .field("expires_in", &self.expires_in)
+            .field("grant_type", &self.grant_type)
+            .field("scope", &self.scope)
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for RefreshTokenPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshTokenPayload")
+            .field("token_type", &self.token_type)
+            .field("scope", &self.scope)
+            .field("refresh_token", &REDACTED)
+            .field("grant_type", &self.grant_type)
+            .field("client_id", &self.client_id)
+            .field("device_token", &self.device_token)
+            .finish()
+    }
+}
+
+impl fmt::Debug for LoginSuccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginSuccess")
+            .field("access_token", &REDACTED)
+            .field("expires_in", &self.expires_in)
+            .field("token_type", &self.token_type)
+            .field("scope", &self.scope)
+            .field("refresh_token", &REDACTED)
+            .field("mfa_code", &self.mfa_code.as_ref().map(|_| REDACTED))
+            .field("backup_code", &self.backup_code.as_ref().map(|_| REDACTED))
+            .finish()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum GrantType {
     #[serde(rename = "password")]
@@ -72,35 +126,28 @@ pub enum GrantType {
     RefreshToken,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
 pub enum Scope {
     #[serde(rename = "internal")]
+    #[default]
     Internal,
 }
 
-impl Default for Scope {
-    fn default() -> Self {
-        Scope::Internal
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
 pub enum TokenType {
+    #[default]
     Bearer,
 }
 
-impl Default for TokenType {
-    fn default() -> Self {
-        TokenType::Bearer
-    }
-}
-
 pub struct MfaLogin {
     username: String,
     password: String,
     device_token: Uuid,
     user_agent: String,
     client_id: String,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    transport: Arc<dyn HttpTransport>,
+    limiter: RateLimiter,
 }
 
 impl MfaLogin {
@@ -113,9 +160,29 @@ impl MfaLogin {
             device_token,
             user_agent: USER_AGENT.to_owned(),
             client_id: CLIENT_ID.to_owned(),
+            middlewares: Vec::new(),
+            transport: Arc::new(ReqwestTransport::new()),
+            limiter: RateLimiter::default(),
         }
     }
 
+    /// Replaces the request budgets of the login requests, they are kept by the logged in
+    /// `Robinhood` client
+    pub fn set_rate_limits(&mut self, limits: RateLimits) {
+        self.limiter = RateLimiter::new(limits);
+    }
+
+    /// Replaces the transport used to log in, it is kept by the logged in `Robinhood` client
+    pub fn set_transport(&mut self, transport: Arc<dyn HttpTransport>) {
+        self.transport = transport;
+    }
+
+    /// Appends a middleware run for the login requests, it is kept by the logged in
+    /// `Robinhood` client
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middlewares.push(middleware);
+    }
+
     /// Build a log in payload based on struct values
     fn build_login_payload(&self) -> LogInPayload {
         LogInPayload {
@@ -130,37 +197,33 @@ impl MfaLogin {
     }
 
     /// Logs into Robinhood in order to request an MFA code (SMS, E-Mail)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "robinhood.request_mfa_code", skip_all)
+    )]
     pub async fn request_mfa_code(&self) -> Result<(), RobinhoodErr> {
         let payload = self.build_login_payload();
-
-        match set_req_headers(
-            self,
-            reqwest::Client::new().post(&format!("{}{}", ROBINHOOD_API_URL, LOG_IN_PATH)),
-        )
-        .json(&payload)
-        .send()
-        .await
-        {
-            Ok(res) => {
-                match res.json::<Value>().await {
-                    Ok(body) => {
-                        if check_invalid_creds(&body) {
-                            return Err(RobinhoodErr::InvalidCredentials);
-                        }
-                    }
-                    Err(e) => {
-                        return Err(RobinhoodErr::RequestError(e));
-                    }
-                };
-            }
-            Err(e) => {
-                return Err(RobinhoodErr::RequestError(e));
-            }
+        self.limiter.acquire(EndpointGroup::Auth).await;
+
+        // Robinhood answers with the MFA challenge, only bad credentials are an error here
+        let (res, body) =
+            post_token(self, &self.middlewares, self.transport.as_ref(), &payload).await?;
+        if check_invalid_creds(&body) {
+            warn!("invalid credentials");
+            return Err(RobinhoodErr::InvalidCredentials);
+        }
+        info!(status = res.status, "mfa code requested");
+        if res.status == 429 || res.status >= 500 {
+            check_status(res)?;
         }
         Ok(())
     }
 
     /// Logs in using an existing MFA code
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "robinhood.log_in", skip_all)
+    )]
     pub async fn log_in(self, mfa_code: String) -> Result<Robinhood, LoginErr> {
         let mut payload = match serde_json::to_value(self.build_login_payload()) {
             Ok(v) => v,
@@ -179,42 +242,29 @@ impl MfaLogin {
             }
         }
         // Make sure mfa_code is in the request body
-        if let None = payload.get("mfa_code") {
+        if payload.get("mfa_code").is_none() {
             return Err(LoginErr::MissingMfaCode);
         }
         // Send request to Robinhood
-        let login_response: LoginSuccess = match set_req_headers(
-            &self,
-            reqwest::Client::new().post(&format!("{}{}", ROBINHOOD_API_URL, LOG_IN_PATH)),
-        )
-        .json(&payload)
-        .send()
-        .await
-        {
-            Ok(v) => match v.json::<Value>().await {
-                Ok(body) => {
-                    if check_invalid_creds(&body) {
-                        return Err(LoginErr::InvalidCredentials);
-                    };
-                    match serde_json::from_value(body) {
-                        Ok(success) => success,
-                        Err(e) => {
-                            let msg = format!(
-                                "Failed to serialize successful login response body: ({})",
-                                e
-                            );
-                            return Err(LoginErr::BadResponseBody(msg));
-                        }
-                    }
-                }
-                Err(e) => {
-                    return Err(LoginErr::RequestError(e));
-                }
-            },
+        self.limiter.acquire(EndpointGroup::Auth).await;
+        let (res, body) =
+            post_token(&self, &self.middlewares, self.transport.as_ref(), &payload).await?;
+        if check_invalid_creds(&body) {
+            warn!("invalid credentials");
+            return Err(LoginErr::InvalidCredentials);
+        };
+        check_status(res)?;
+        let login_response: LoginSuccess = match serde_json::from_value(body) {
+            Ok(success) => success,
             Err(e) => {
-                return Err(LoginErr::RequestError(e));
+                let msg = format!(
+                    "Failed to serialize successful login response body: ({})",
+                    e
+                );
+                return Err(LoginErr::BadResponseBody(msg));
             }
         };
+        info!(expires_in = login_response.expires_in, "logged in");
         // Build a Robinhood session
         Ok(Robinhood {
             device_token: self.device_token,
@@ -225,6 +275,14 @@ impl MfaLogin {
             refresh_token: login_response.refresh_token,
             token_expires_in: login_response.expires_in,
             auto_refresh: true,
+            risk: RiskGuard::default(),
+            paper: None,
+            limiter: self.limiter,
+            retry: RetryPolicy::default(),
+            metrics: None,
+            middlewares: self.middlewares,
+            transport: self.transport,
+            quotes: QuoteHub::default(),
         })
     }
 
@@ -262,9 +320,7 @@ impl MfaLogin {
                 self.device_token = new_device_token;
                 Ok(())
             }
-            Err(e) => {
-                return Err(e);
-            }
+            Err(e) => Err(e),
         }
     }
 
@@ -299,7 +355,7 @@ impl Robinhood {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// use robinhood;
     ///
     /// #[tokio::main]
@@ -314,15 +370,13 @@ impl Robinhood {
     ///     // Needs to be `mut` will revise this in the future
     ///     let mut robinhood_client = mfa_client.log_in(mfa_code).await?;
     ///
-    ///     // Get the price of SPY in an interval
+    ///     // Follow the price of SPY, a quote is emitted every time it changes
+    ///     use futures::StreamExt;
     ///     use std::time::Duration;
-    ///     use std::thread;
     ///
-    ///     loop {
-    ///         // Use some timer to not spam Robinhood with requests.. you might get banned
-    ///         thread::sleep(Duration::from_millis(500));
-    ///         let price: usize = robinhood_client.get_price("SPY").await?;
-    ///         println!("{}", price);
+    ///     let mut quotes = robinhood_client.quote_stream(&["SPY"], Duration::from_secs(1));
+    ///     while let Some(quote) = quotes.next().await {
+    ///         println!("{}", quote?.last_trade_price);
     ///     }
     ///
     /// }
@@ -338,7 +392,7 @@ impl Robinhood {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// use robinhood;
     /// use uuid;
     ///
@@ -353,15 +407,13 @@ impl Robinhood {
     ///     // If you have a valid refresh token
     ///     // and `Robinhood.auto_refresh` is set to true then it will create a new one.
     ///
-    ///     // Get the price of SPY in an interval
+    ///     // Follow the price of SPY, a quote is emitted every time it changes
+    ///     use futures::StreamExt;
     ///     use std::time::Duration;
-    ///     use std::thread;
     ///
-    ///     loop {
-    ///         // Use some timer to not spam Robinhood with requests.. you might get banned
-    ///         thread::sleep(Duration::from_millis(500));
-    ///         let price: usize = robinhood_client.get_price("SPY").await?;
-    ///         println!("{}", price);
+    ///     let mut quotes = robinhood_client.quote_stream(&["SPY"], Duration::from_secs(1));
+    ///     while let Some(quote) = quotes.next().await {
+    ///         println!("{}", quote?.last_trade_price);
     ///     }
     ///
     /// }
@@ -380,6 +432,14 @@ impl Robinhood {
             refresh_token,
             token_expires_in: EXPIRES_IN,
             auto_refresh: true,
+            risk: RiskGuard::default(),
+            paper: None,
+            limiter: RateLimiter::default(),
+            retry: RetryPolicy::default(),
+            metrics: None,
+            middlewares: Vec::new(),
+            transport: Arc::new(ReqwestTransport::new()),
+            quotes: QuoteHub::default(),
         }
     }
 
@@ -427,9 +487,7 @@ impl Robinhood {
                 self.device_token = new_device_token;
                 Ok(())
             }
-            Err(e) => {
-                return Err(e);
-            }
+            Err(e) => Err(e),
         }
     }
 
@@ -463,6 +521,10 @@ impl Robinhood {
     }
 
     // Necessary after every 24h since access_token has an expiration of 24h
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "robinhood.refresh_token", skip_all)
+    )]
     pub async fn refresh_token(
         &mut self,
         old_refresh_token: Option<String>,
@@ -470,10 +532,21 @@ impl Robinhood {
         // Make sure there is no data race when updating the token
         if let Some(old_token) = old_refresh_token {
             if self.refresh_token != old_token {
+                debug!("token already refreshed");
                 return Ok(None);
             };
         }
+        let refreshed = self.request_new_token().await;
+        let outcome = if refreshed.is_ok() {
+            "success"
+        } else {
+            "failure"
+        };
+        self.count(TOKEN_REFRESHES_TOTAL, &[("outcome", outcome)]);
+        refreshed.map(Some)
+    }
 
+    async fn request_new_token(&mut self) -> Result<NewToken, RefreshTokenErr> {
         let req_token_payload = RefreshTokenPayload {
             client_id: CLIENT_ID.to_owned(),
             device_token: self.device_token,
@@ -482,44 +555,40 @@ impl Robinhood {
             scope: Scope::Internal,
             token_type: TokenType::Bearer,
         };
-        let req = reqwest::Client::new().post(&format!("{}{}", ROBINHOOD_API_URL, LOG_IN_PATH));
-        let login_response: LoginSuccess = match set_req_headers(self, req)
-            .json(&req_token_payload)
-            .send()
-            .await
-        {
-            Ok(v) => match v.json::<Value>().await {
-                Ok(body) => {
-                    // Check if refresh_token was invalid
-                    if let Some(err_msg) = body["error"].as_str() {
-                        if err_msg == "invalid_grant" {
-                            return Err(RefreshTokenErr::BadRefreshToken(
-                                self.refresh_token.clone(),
-                            ));
-                        }
-                    }
-                    match serde_json::from_value::<LoginSuccess>(body) {
-                        Ok(success) => success,
-                        Err(e) => {
-                            let msg = format!(
-                                "Failed to serialize successful login response body: ({})",
-                                e
-                            );
-                            return Err(RefreshTokenErr::WrongResponseBody(msg));
-                        }
-                    }
-                }
-                Err(e) => return Err(RefreshTokenErr::RequestError(e)),
-            },
-            Err(e) => return Err(RefreshTokenErr::RequestError(e)),
+        let waited = self.limiter.acquire(EndpointGroup::Auth).await;
+        let group = [("group", EndpointGroup::Auth.as_str())];
+        self.observe(RATE_LIMITER_WAIT_SECONDS, &group, waited.as_secs_f64());
+        let (res, body) = post_token(
+            self,
+            &self.middlewares,
+            self.transport.as_ref(),
+            &req_token_payload,
+        )
+        .await?;
+        // Check if refresh_token was invalid
+        if body["error"].as_str() == Some("invalid_grant") {
+            warn!("refresh token rejected");
+            return Err(RefreshTokenErr::BadRefreshToken);
+        }
+        check_status(res)?;
+        let login_response = match serde_json::from_value::<LoginSuccess>(body) {
+            Ok(success) => success,
+            Err(e) => {
+                let msg = format!(
+                    "Failed to serialize successful login response body: ({})",
+                    e
+                );
+                return Err(RefreshTokenErr::BadResponseBody(msg));
+            }
         };
         self.refresh_token = login_response.refresh_token;
         self.token = login_response.access_token;
         self.token_expires_in = login_response.expires_in;
-        Ok(Some(NewToken {
+        info!(expires_in = self.token_expires_in, "token refreshed");
+        Ok(NewToken {
             token: self.token.clone(),
             refresh_token: self.refresh_token.clone(),
-        }))
+        })
     }
 }
 
@@ -533,11 +602,101 @@ impl AgentToken for Robinhood {
     }
 }
 
+// Posts to the token endpoint. Its error bodies are part of the login flow so the
+// parsed body is returned along with the response whatever the status
+async fn post_token<T: AgentToken, P: Serialize>(
+    requestor: &T,
+    middlewares: &[Arc<dyn Middleware>],
+    transport: &dyn HttpTransport,
+    payload: &P,
+) -> Result<(HttpResponse, Value), RobinhoodErr> {
+    let body = match serde_json::to_value(payload) {
+        Ok(body) => body,
+        Err(e) => return Err(RobinhoodErr::BadLoginBody(e.to_string())),
+    };
+    let request = HttpRequest {
+        method: ReqKind::Post,
+        url: format!("{}{}", ROBINHOOD_API_URL, LOG_IN_PATH),
+        headers: req_headers(requestor),
+        body: Some(body),
+        timeout: None,
+    };
+    let res = execute(middlewares, transport, request).await?;
+    match decode::<Value>(&res) {
+        Ok(body) => Ok((res, body)),
+        // Not JSON, most likely an error page
+        Err(e) => {
+            check_status(res)?;
+            Err(e)
+        }
+    }
+}
+
 pub fn check_invalid_creds(body: &Value) -> bool {
     if let Some(detail_msg) = body["detail"].as_str() {
         if detail_msg.contains("Unable to log in with provided credentials") {
             return true;
         }
     }
-    return false;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_output_redacts_secrets() {
+        let payload = MfaLogin::new("user".to_owned(), "hunter2".to_owned()).build_login_payload();
+        let debug = format!("{:?}", payload);
+        assert!(debug.contains("user") && !debug.contains("hunter2"));
+
+        let success = LoginSuccess {
+            access_token: "access".to_owned(),
+            refresh_token: "refresh".to_owned(),
+            mfa_code: Some("123456".to_owned()),
+            ..LoginSuccess::default()
+        };
+        let debug = format!("{:?}", success);
+        assert!(!debug.contains("access\"") && !debug.contains("refresh\""));
+        assert!(!debug.contains("123456"));
+    }
+
+    // Answers every login with an MFA challenge
+    struct Challenge;
+
+    impl HttpTransport for Challenge {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> futures::future::BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            use futures::FutureExt;
+            futures::future::ready(Ok(HttpResponse {
+                status: 400,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: r#"{"mfa_required": true, "mfa_type": "sms"}"#.to_owned(),
+            }))
+            .boxed()
+        }
+    }
+
+    #[test]
+    fn login_requests_share_the_auth_budget() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let mut mfa_client = MfaLogin::new("user".to_owned(), "hunter2".to_owned());
+        mfa_client.set_transport(Arc::new(Challenge));
+        mfa_client.set_rate_limits(RateLimits {
+            auth: Some(crate::RateLimit::per_second(20).with_burst(1)),
+            ..RateLimits::unlimited()
+        });
+        let started = std::time::Instant::now();
+        for _ in 0..3 {
+            runtime.block_on(mfa_client.request_mfa_code()).unwrap();
+        }
+        assert!(started.elapsed() >= std::time::Duration::from_millis(90));
+    }
 }
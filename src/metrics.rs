@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use crate::error::RobinhoodErr;
+use crate::Robinhood;
+
+/// Requests by `endpoint`, `method` and `status` (the HTTP status, `timeout` or `network`)
+pub const REQUESTS_TOTAL: &str = "robinhood_requests_total";
+/// Latency of every attempt by `endpoint` and `method`
+pub const REQUEST_DURATION_SECONDS: &str = "robinhood_request_duration_seconds";
+/// Retried attempts by `endpoint` and `reason`
+pub const RETRIES_TOTAL: &str = "robinhood_retries_total";
+/// Requests that failed for good by `endpoint` and `kind`
+pub const REQUEST_FAILURES_TOTAL: &str = "robinhood_request_failures_total";
+/// Time spent waiting for the rate limiter by endpoint `group`
+pub const RATE_LIMITER_WAIT_SECONDS: &str = "robinhood_rate_limiter_wait_seconds";
+/// Token refreshes by `outcome` (`success` or `failure`)
+pub const TOKEN_REFRESHES_TOTAL: &str = "robinhood_token_refreshes_total";
+
+/// Responses by `endpoint`, `method` and `status`, counted by `MetricsMiddleware`
+pub const HTTP_RESPONSES_TOTAL: &str = "robinhood_http_responses_total";
+/// Size of response bodies by `endpoint`, observed by `MetricsMiddleware`
+pub const HTTP_RESPONSE_BYTES: &str = "robinhood_http_response_bytes";
+
+/// Receives the metrics of a session, see `Robinhood::set_metrics_sink`
+///
+/// Label values are kept to a small set: endpoints have their ids replaced by `:id`.
+pub trait MetricsSink: Send + Sync {
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64);
+    fn observe_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+fn owned(labels: &[(&'static str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(name, value)| (*name, (*value).to_owned()))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    // Cumulative count per upper bound of the buckets of the metric
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    counters: BTreeMap<(&'static str, Labels), u64>,
+    histograms: BTreeMap<(&'static str, Labels), Histogram>,
+}
+
+/// A `MetricsSink` keeping everything in memory and rendering it in the
+/// Prometheus text exposition format
+///
+/// # Example
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use robinhood::PrometheusSink;
+///
+/// let metrics = Arc::new(PrometheusSink::new());
+/// robinhood_client.set_metrics_sink(metrics.clone());
+/// // Serve this from the /metrics endpoint of your app
+/// let body = metrics.render();
+/// ```
+#[derive(Debug)]
+pub struct PrometheusSink {
+    buckets: Vec<f64>,
+    // Buckets of the metrics that aren't measured in seconds
+    metric_buckets: BTreeMap<&'static str, Vec<f64>>,
+    series: Mutex<Series>,
+}
+
+impl Default for PrometheusSink {
+    fn default() -> Self {
+        PrometheusSink::new()
+    }
+}
+
+impl PrometheusSink {
+    /// A sink with latency buckets from 5ms to 10s, and buckets from 256B to 4MiB for
+    /// `HTTP_RESPONSE_BYTES`
+    pub fn new() -> Self {
+        PrometheusSink::with_buckets(vec![
+            0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+        ])
+    }
+
+    /// A sink using `buckets` as the upper bounds of every histogram without buckets of
+    /// its own, `HTTP_RESPONSE_BYTES` keeps its byte buckets
+    pub fn with_buckets(buckets: Vec<f64>) -> Self {
+        let bytes = (0..8).map(|i| 256.0 * 4f64.powi(i)).collect();
+        PrometheusSink {
+            buckets: sorted(buckets),
+            metric_buckets: BTreeMap::new(),
+            series: Mutex::new(Series::default()),
+        }
+        .with_metric_buckets(HTTP_RESPONSE_BYTES, bytes)
+    }
+
+    /// Uses `buckets` as the upper bounds of the histogram `name`
+    pub fn with_metric_buckets(mut self, name: &'static str, buckets: Vec<f64>) -> Self {
+        self.metric_buckets.insert(name, sorted(buckets));
+        self
+    }
+
+    fn buckets_of(&self, name: &str) -> &[f64] {
+        self.metric_buckets.get(name).unwrap_or(&self.buckets)
+    }
+
+    /// Every metric recorded so far in the text exposition format
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap();
+        let mut out = String::new();
+        let mut last = "";
+        for ((name, labels), value) in &series.counters {
+            if *name != last {
+                let _ = writeln!(out, "# TYPE {} counter", name);
+                last = name;
+            }
+            let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), value);
+        }
+        last = "";
+        for ((name, labels), histogram) in &series.histograms {
+            if *name != last {
+                let _ = writeln!(out, "# TYPE {} histogram", name);
+                last = name;
+            }
+            for (bound, count) in self.buckets_of(name).iter().zip(&histogram.buckets) {
+                let le = bound.to_string();
+                let labels = render_labels(labels, Some(&le));
+                let _ = writeln!(out, "{}_bucket{} {}", name, labels, count);
+            }
+            let labels_inf = render_labels(labels, Some("+Inf"));
+            let _ = writeln!(out, "{}_bucket{} {}", name, labels_inf, histogram.count);
+            let labels = render_labels(labels, None);
+            let _ = writeln!(out, "{}_sum{} {}", name, labels, histogram.sum);
+            let _ = writeln!(out, "{}_count{} {}", name, labels, histogram.count);
+        }
+        out
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        let mut series = self.series.lock().unwrap();
+        *series.counters.entry((name, owned(labels))).or_insert(0) += value;
+    }
+
+    fn observe_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        let bounds = self.buckets_of(name);
+        let mut series = self.series.lock().unwrap();
+        let histogram = series
+            .histograms
+            .entry((name, owned(labels)))
+            .or_insert_with(|| Histogram {
+                buckets: vec![0; bounds.len()],
+                sum: 0.0,
+                count: 0,
+            });
+        for (bound, count) in bounds.iter().zip(histogram.buckets.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+}
+
+fn sorted(mut buckets: Vec<f64>) -> Vec<f64> {
+    buckets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    buckets
+}
+
+fn render_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        return String::new();
+    }
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The path of `url` with every id, symbol and account number replaced by `:id`
+/// so that it can be used as a label
+pub(crate) fn endpoint_label(url: &str) -> String {
+    let path = url.split('?').next().unwrap_or(url);
+    let path = match path.find("://") {
+        Some(scheme) => match path[scheme + 3..].find('/') {
+            Some(host) => &path[scheme + 3 + host + 1..],
+            None => "",
+        },
+        None => path,
+    };
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(|segment| {
+            let is_static = segment.starts_with(|c: char| c.is_ascii_lowercase())
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if segment.is_empty() || is_static {
+                segment
+            } else {
+                ":id"
+            }
+        })
+        .collect();
+    segments.join("/")
+}
+
+/// Label of the reason a request failed or was retried
+pub(crate) fn error_kind(error: &RobinhoodErr) -> &'static str {
+    match error {
+        RobinhoodErr::Unauthorized(_) => "unauthorized",
+        RobinhoodErr::NotFound(_) => "not_found",
+        RobinhoodErr::Validation(_) => "validation",
+        RobinhoodErr::Forbidden(_) => "forbidden",
+        RobinhoodErr::RateLimited { .. } => "rate_limited",
+        RobinhoodErr::Server(_) => "server",
+        RobinhoodErr::Api(_) => "api",
+        RobinhoodErr::Decode { .. } => "decode",
+        RobinhoodErr::Timeout { .. } => "timeout",
+        RobinhoodErr::Network { .. } => "network",
+        RobinhoodErr::Transport { .. } => "transport",
+        _ => "other",
+    }
+}
+
+impl Robinhood {
+    /// Sends the metrics of every request of the session to `sink`
+    pub fn set_metrics_sink(&mut self, sink: Arc<dyn MetricsSink>) {
+        self.metrics = Some(sink);
+    }
+
+    /// Stops recording metrics
+    pub fn clear_metrics_sink(&mut self) {
+        self.metrics = None;
+    }
+
+    pub(crate) fn count(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_counter(name, labels, 1);
+        }
+    }
+
+    pub(crate) fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_histogram(name, labels, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_drop_ids() {
+        let label = |url| endpoint_label(url);
+        assert_eq!(
+            label("https://api.robinhood.com/orders/6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a/cancel/"),
+            "orders/:id/cancel/"
+        );
+        assert_eq!(
+            label("https://api.robinhood.com/quotes/SPY/"),
+            "quotes/:id/"
+        );
+        assert_eq!(
+            label("https://api.robinhood.com/oauth2/token/"),
+            "oauth2/token/"
+        );
+        assert_eq!(
+            label("https://api.robinhood.com/accounts/5QR12345/?nonzero=true"),
+            "accounts/:id/"
+        );
+    }
+
+    #[test]
+    fn renders_text_exposition() {
+        let sink = PrometheusSink::with_buckets(vec![1.0, 0.1]);
+        let labels = [("endpoint", "quotes/"), ("method", "GET")];
+        sink.increment_counter(REQUESTS_TOTAL, &[("status", "429")], 1);
+        sink.increment_counter(REQUESTS_TOTAL, &[("status", "429")], 2);
+        sink.observe_histogram(REQUEST_DURATION_SECONDS, &labels, 0.05);
+        sink.observe_histogram(REQUEST_DURATION_SECONDS, &labels, 0.5);
+        let expected = "\
+# TYPE robinhood_requests_total counter
+robinhood_requests_total{status=\"429\"} 3
+# TYPE robinhood_request_duration_seconds histogram
+robinhood_request_duration_seconds_bucket{endpoint=\"quotes/\",method=\"GET\",le=\"0.1\"} 1
+robinhood_request_duration_seconds_bucket{endpoint=\"quotes/\",method=\"GET\",le=\"1\"} 2
+robinhood_request_duration_seconds_bucket{endpoint=\"quotes/\",method=\"GET\",le=\"+Inf\"} 2
+robinhood_request_duration_seconds_sum{endpoint=\"quotes/\",method=\"GET\"} 0.55
+robinhood_request_duration_seconds_count{endpoint=\"quotes/\",method=\"GET\"} 2
+";
+        assert_eq!(sink.render(), expected);
+    }
+
+    #[test]
+    fn response_sizes_have_byte_buckets() {
+        let sink = PrometheusSink::new();
+        sink.observe_histogram(HTTP_RESPONSE_BYTES, &[("endpoint", "quotes/")], 2000.0);
+        let rendered = sink.render();
+        let bucket = |le: &str, count: u64| {
+            format!(
+                "robinhood_http_response_bytes_bucket{{endpoint=\"quotes/\",le=\"{}\"}} {}",
+                le, count
+            )
+        };
+        assert!(rendered.contains(&bucket("1024", 0)));
+        assert!(rendered.contains(&bucket("4096", 1)));
+        assert!(rendered.contains(&bucket("4194304", 1)));
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use futures::future::{self, BoxFuture, FutureExt};
+use serde_json::Value;
+
+use crate::error::RobinhoodErr;
+use crate::http::{HttpRequest, HttpResponse};
+use crate::metrics::{endpoint_label, MetricsSink, HTTP_RESPONSES_TOTAL, HTTP_RESPONSE_BYTES};
+use crate::trace::REDACTED;
+use crate::Robinhood;
+
+/// Hooks run around every request of a session, including login and token refresh
+///
+/// `before_request` hooks run in the order the middlewares were added and may change
+/// the request. `after_response` hooks run in reverse order and see the raw response
+/// before it is checked and decoded. Returning an error from either aborts the call.
+/// Retried requests go through the chain once per attempt.
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::future::{BoxFuture, FutureExt};
+/// use robinhood::{HttpRequest, Middleware, ReqKind, RobinhoodErr};
+///
+/// struct OrderAudit;
+///
+/// impl Middleware for OrderAudit {
+///     fn before_request<'a>(
+///         &'a self,
+///         request: &'a mut HttpRequest,
+///     ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+///         async move {
+///             if request.method == ReqKind::Post && request.url.contains("orders/") {
+///                 println!("order sent: {:?}", request.body);
+///             }
+///             Ok(())
+///         }
+///         .boxed()
+///     }
+/// }
+///
+/// robinhood_client.add_middleware(Arc::new(OrderAudit));
+/// ```
+pub trait Middleware: Send + Sync {
+    fn before_request<'a>(
+        &'a self,
+        _request: &'a mut HttpRequest,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        future::ready(Ok(())).boxed()
+    }
+
+    fn after_response<'a>(
+        &'a self,
+        _request: &'a HttpRequest,
+        _response: &'a mut HttpResponse,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        future::ready(Ok(())).boxed()
+    }
+}
+
+// Fields of login, token and bank account payloads and responses never shown by
+// `LoggingMiddleware`
+const SECRET_FIELDS: [&str; 8] = [
+    "password",
+    "mfa_code",
+    "access_token",
+    "refresh_token",
+    "backup_code",
+    "token",
+    "bank_account_number",
+    "bank_routing_number",
+];
+
+/// Copy of a JSON value with every secret field replaced
+pub(crate) fn redact_json(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| {
+                    if SECRET_FIELDS.contains(&name.as_str()) {
+                        (name.clone(), Value::String(REDACTED.to_owned()))
+                    } else {
+                        (name.clone(), redact_json(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(redact_json).collect()),
+        value => value.clone(),
+    }
+}
+
+pub(crate) fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) => redact_json(&value).to_string(),
+        Err(_) => body.to_owned(),
+    }
+}
+
+/// Writes a line per request and response to a callback, with secrets redacted
+///
+/// The `Authorization` header is never logged. Bodies are only logged after
+/// `with_bodies(true)`.
+pub struct LoggingMiddleware {
+    log: Box<dyn Fn(&str) + Send + Sync>,
+    bodies: bool,
+}
+
+impl LoggingMiddleware {
+    pub fn new<F: Fn(&str) + Send + Sync + 'static>(log: F) -> Self {
+        LoggingMiddleware {
+            log: Box::new(log),
+            bodies: false,
+        }
+    }
+
+    /// Logs to standard error
+    pub fn stderr() -> Self {
+        LoggingMiddleware::new(|line| eprintln!("{}", line))
+    }
+
+    /// Also logs request and response bodies
+    pub fn with_bodies(mut self, bodies: bool) -> Self {
+        self.bodies = bodies;
+        self
+    }
+}
+
+impl Middleware for LoggingMiddleware {
+    fn before_request<'a>(
+        &'a self,
+        request: &'a mut HttpRequest,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        let mut line = format!("--> {} {}", request.method.as_str(), request.url);
+        if let (true, Some(body)) = (self.bodies, &request.body) {
+            line.push_str(&format!(" {}", redact_json(body)));
+        }
+        (self.log)(&line);
+        future::ready(Ok(())).boxed()
+    }
+
+    fn after_response<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+        response: &'a mut HttpResponse,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        let mut line = format!(
+            "<-- {} {} {} ({} bytes)",
+            response.status,
+            request.method.as_str(),
+            request.url,
+            response.body.len()
+        );
+        if self.bodies {
+            line.push_str(&format!(" {}", redact_body(&response.body)));
+        }
+        (self.log)(&line);
+        future::ready(Ok(())).boxed()
+    }
+}
+
+/// Counts every response and its size, login and token refresh included
+///
+/// The client's own metrics (see `Robinhood::set_metrics_sink`) don't cover the
+/// login of an `MfaLogin`, add this middleware to it to count those too.
+pub struct MetricsMiddleware {
+    sink: Arc<dyn MetricsSink>,
+}
+
+impl MetricsMiddleware {
+    pub fn new(sink: Arc<dyn MetricsSink>) -> Self {
+        MetricsMiddleware { sink }
+    }
+}
+
+impl Middleware for MetricsMiddleware {
+    fn after_response<'a>(
+        &'a self,
+        request: &'a HttpRequest,
+        response: &'a mut HttpResponse,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        let endpoint = endpoint_label(&request.url);
+        let status = response.status.to_string();
+        let labels = [
+            ("endpoint", endpoint.as_str()),
+            ("method", request.method.as_str()),
+            ("status", status.as_str()),
+        ];
+        self.sink
+            .increment_counter(HTTP_RESPONSES_TOTAL, &labels, 1);
+        let labels = [("endpoint", endpoint.as_str())];
+        let bytes = response.body.len() as f64;
+        self.sink
+            .observe_histogram(HTTP_RESPONSE_BYTES, &labels, bytes);
+        future::ready(Ok(())).boxed()
+    }
+}
+
+/// Adds fixed headers to every request, replacing any header of the same name
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMiddleware {
+    headers: Vec<(String, String)>,
+}
+
+impl HeaderMiddleware {
+    pub fn new() -> Self {
+        HeaderMiddleware::default()
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+impl Middleware for HeaderMiddleware {
+    fn before_request<'a>(
+        &'a self,
+        request: &'a mut HttpRequest,
+    ) -> BoxFuture<'a, Result<(), RobinhoodErr>> {
+        for (name, value) in &self.headers {
+            request.set_header(name, value);
+        }
+        future::ready(Ok(())).boxed()
+    }
+}
+
+impl Robinhood {
+    /// Appends a middleware to the chain run for every request, see `Middleware`
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    /// Removes every middleware
+    pub fn clear_middlewares(&mut self) {
+        self.middlewares.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::req::ReqKind;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[test]
+    fn logging_redacts_secrets() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let logged = lines.clone();
+        let logging =
+            LoggingMiddleware::new(move |line| logged.lock().unwrap().push(line.to_owned()))
+                .with_bodies(true);
+        let headers = HeaderMiddleware::new().header("X-Trace", "1");
+
+        let mut request = HttpRequest {
+            method: ReqKind::Post,
+            url: "https://api.robinhood.com/oauth2/token/".to_owned(),
+            headers: vec![("Authorization".to_owned(), "Bearer secret".to_owned())],
+            body: Some(json!({"username": "user", "password": "hunter2", "mfa_code": "123456"})),
+            timeout: None,
+        };
+        let mut response = HttpResponse {
+            status: 200,
+            url: request.url.clone(),
+            headers: Vec::new(),
+            body: r#"{"access_token": "abc", "expires_in": 86400}"#.to_owned(),
+        };
+        block_on(headers.before_request(&mut request)).unwrap();
+        block_on(logging.before_request(&mut request)).unwrap();
+        block_on(logging.after_response(&request, &mut response)).unwrap();
+
+        assert_eq!(request.header("x-trace"), Some("1"));
+        let lines = lines.lock().unwrap().join("\n");
+        assert!(lines.contains("user") && lines.contains("86400"));
+        for secret in ["secret", "hunter2", "123456", "abc"] {
+            assert!(!lines.contains(secret), "{} in {}", secret, lines);
+        }
+    }
+}
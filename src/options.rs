@@ -0,0 +1,14 @@
+mod chain;
+mod market_data;
+mod orders;
+mod positions;
+
+pub use chain::{MinTicks, OptionChain, OptionInstrument, OptionType, UnderlyingInstrument};
+pub use market_data::{Greeks, OptionMarketData};
+pub use orders::{
+    Direction, OptionLeg, OptionOrder, OptionOrderLeg, OptionOrderRequest, PositionEffect,
+};
+pub use positions::{
+    AggregateOptionLeg, AggregateOptionPosition, OptionExposure, OptionPosition,
+    OptionPositionType, ValuedOptionPosition,
+};
@@ -0,0 +1,310 @@
+use std::ops::RangeBounds;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, OPTIONS_CHAINS_PATH, OPTIONS_INSTRUMENTS_PATH, ROBINHOOD_API_URL};
+
+impl Robinhood {
+    /// Calls api.robinhood.com/options/chains/?equity_instrument_ids=(id) for the instrument
+    /// behind `symbol` and returns its `OptionChain`
+    pub async fn get_option_chain(&self, symbol: &str) -> Result<OptionChain, RobinhoodErr> {
+        let quote = self.get_quote(symbol).await?;
+        let url = &format!(
+            "{}{}?equity_instrument_ids={}",
+            ROBINHOOD_API_URL, OPTIONS_CHAINS_PATH, quote.instrument_id
+        );
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        let chains = decode::<OptionChainsResponse>(&response)?;
+        // Adjusted chains (after splits and mergers) are listed next to the regular one
+        let index = chains
+            .results
+            .iter()
+            .position(|chain| chain.symbol.eq_ignore_ascii_case(symbol))
+            .unwrap_or(0);
+        match chains.results.into_iter().nth(index) {
+            Some(chain) => Ok(chain),
+            None => Err(RobinhoodErr::NotFound(url.to_string())),
+        }
+    }
+
+    /// Lists the active option instruments of `symbol` expiring on `expiration` ("2021-03-19")
+    ///
+    /// `option_type` restricts the list to calls or puts and only instruments with
+    /// a strike within `strikes` are returned.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::OptionType;
+    ///
+    /// let chain = robinhood_client.get_option_chain("SPY").await?;
+    /// let expiration = &chain.expiration_dates[0];
+    /// let calls = robinhood_client
+    ///     .find_options("SPY", expiration, Some(OptionType::Call), 380.0..=400.0)
+    ///     .await?;
+    /// let puts = robinhood_client.find_options("SPY", expiration, Some(OptionType::Put), ..).await?;
+    /// ```
+    pub async fn find_options<R: RangeBounds<f64>>(
+        &self,
+        symbol: &str,
+        expiration: &str,
+        option_type: Option<OptionType>,
+        strikes: R,
+    ) -> Result<Vec<OptionInstrument>, RobinhoodErr> {
+        let chain = self.get_option_chain(symbol).await?;
+        let mut url = format!(
+            "{}{}?chain_id={}&expiration_dates={}&state=active",
+            ROBINHOOD_API_URL, OPTIONS_INSTRUMENTS_PATH, chain.id, expiration
+        );
+        if let Some(option_type) = option_type {
+            url.push_str(match option_type {
+                OptionType::Call => "&type=call",
+                OptionType::Put => "&type=put",
+            });
+        }
+        let mut instruments = Vec::new();
+        let mut pages = self.paginate::<OptionInstrument>(&url);
+        while let Some(instrument) = pages.next().await {
+            let instrument = instrument?;
+            if strikes.contains(&instrument.strike_price.parse::<f64>()?) {
+                instruments.push(instrument);
+            }
+        }
+        instruments.sort_by(|a, b| {
+            let a = a.strike_price.parse::<f64>().unwrap_or_default();
+            let b = b.strike_price.parse::<f64>().unwrap_or_default();
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(instruments)
+    }
+
+    /// Calls api.robinhood.com/options/instruments/(id)/ and returns the body as `OptionInstrument`
+    pub async fn get_option_instrument(&self, id: &str) -> Result<OptionInstrument, RobinhoodErr> {
+        let url = &format!("{}{}{}/", ROBINHOOD_API_URL, OPTIONS_INSTRUMENTS_PATH, id);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<OptionInstrument>(&response)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OptionChainsResponse {
+    results: Vec<OptionChain>,
+}
+
+// "above_tick": "0.05",
+// "below_tick": "0.01",
+// "cutoff_price": "3.00"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct MinTicks {
+    pub above_tick: Option<String>,
+    pub below_tick: Option<String>,
+    pub cutoff_price: Option<String>,
+}
+
+// "id": "c277b118-58d9-4060-8dc5-a3b5898955cb",
+// "instrument": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "quantity": 100
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct UnderlyingInstrument {
+    pub id: String,
+    pub instrument: String,
+    pub quantity: usize,
+}
+
+// "id": "c277b118-58d9-4060-8dc5-a3b5898955cb",
+// "symbol": "SPY",
+// "can_open_position": true,
+// "cash_component": null,
+// "expiration_dates": ["2021-03-05", "2021-03-08", "2021-03-10"],
+// "trade_value_multiplier": "100.0000",
+// "underlying_instruments": [{...}],
+// "min_ticks": {"above_tick": "0.01", "below_tick": "0.01", "cutoff_price": "0.00"}
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct OptionChain {
+    pub id: String,
+    pub symbol: String,
+    pub can_open_position: bool,
+    pub cash_component: Option<String>,
+    pub expiration_dates: Vec<String>,
+    pub trade_value_multiplier: String,
+    pub underlying_instruments: Vec<UnderlyingInstrument>,
+    pub min_ticks: MinTicks,
+}
+
+// "id": "e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e",
+// "url": "https://api.robinhood.com/options/instruments/e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e/",
+// "chain_id": "c277b118-58d9-4060-8dc5-a3b5898955cb",
+// "chain_symbol": "SPY",
+// "type": "call",
+// "strike_price": "390.0000",
+// "expiration_date": "2021-03-19",
+// "issue_date": "2020-12-18",
+// "state": "active",
+// "tradability": "tradable",
+// "rhs_tradability": "tradable",
+// "min_ticks": {"above_tick": "0.01", "below_tick": "0.01", "cutoff_price": "0.00"},
+// "created_at": "2020-12-18T02:07:44.148442Z",
+// "updated_at": "2020-12-18T02:07:44.148448Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptionInstrument {
+    pub id: String,
+    pub url: String,
+    pub chain_id: String,
+    pub chain_symbol: String,
+    #[serde(rename = "type")]
+    pub option_type: OptionType,
+    pub strike_price: String,
+    pub expiration_date: String,
+    pub issue_date: String,
+    pub state: String,
+    pub tradability: String,
+    pub rhs_tradability: String,
+    pub min_ticks: MinTicks,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl OptionInstrument {
+    /// `true` while new positions can be opened on the contract
+    pub fn is_tradable(&self) -> bool {
+        self.state == "active" && self.tradability == "tradable"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    use crate::{HttpRequest, HttpResponse, HttpTransport, QuotesResponse, RateLimits};
+
+    // Answers the quote, chains and instruments endpoints, keeps the requested URLs
+    #[derive(Default)]
+    struct Chains {
+        sent: Mutex<Vec<String>>,
+    }
+
+    fn chain(id: &str, symbol: &str) -> OptionChain {
+        OptionChain {
+            id: id.to_owned(),
+            symbol: symbol.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn instrument(option_type: &str, strike_price: &str) -> Value {
+        json!({
+            "id": strike_price, "url": "", "chain_id": "regular", "chain_symbol": "SPY",
+            "type": option_type, "strike_price": strike_price,
+            "expiration_date": "2021-03-19", "issue_date": "2020-12-18", "state": "active",
+            "tradability": "tradable", "rhs_tradability": "tradable", "min_ticks": {},
+            "created_at": "", "updated_at": "",
+        })
+    }
+
+    impl HttpTransport for Chains {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            self.sent.lock().unwrap().push(request.url.clone());
+            let body = if request.url.contains("/quotes/") {
+                serde_json::to_value(QuotesResponse {
+                    symbol: "SPY".to_owned(),
+                    instrument_id: "8f92e76f".to_owned(),
+                    ..Default::default()
+                })
+                .unwrap()
+            } else if request.url.contains(OPTIONS_CHAINS_PATH) {
+                // The adjusted chain of a past split comes first
+                json!({ "results": [chain("adjusted", "1SPY"), chain("regular", "SPY")] })
+            } else {
+                let strikes = ["410.0000", "380.0000", "370.0000", "390.0000", "400.0000"];
+                let results: Vec<Value> = strikes
+                    .iter()
+                    .map(|strike| instrument("call", strike))
+                    .collect();
+                json!({ "results": results, "next": null })
+            };
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn client(transport: Arc<Chains>) -> Robinhood {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        client.set_transport(transport);
+        client.set_rate_limits(RateLimits::unlimited());
+        client
+    }
+
+    #[test]
+    fn picks_the_chain_of_the_symbol() {
+        let client = client(Arc::new(Chains::default()));
+        let chain = block_on(client.get_option_chain("spy")).unwrap();
+        assert_eq!(chain.id, "regular");
+    }
+
+    #[test]
+    fn filters_options_by_expiration_type_and_strike() {
+        let transport = Arc::new(Chains::default());
+        let client = client(transport.clone());
+        let calls = block_on(client.find_options(
+            "SPY",
+            "2021-03-19",
+            Some(OptionType::Call),
+            380.0..400.0,
+        ))
+        .unwrap();
+        let strikes: Vec<&str> = calls.iter().map(|c| c.strike_price.as_str()).collect();
+        assert_eq!(strikes, vec!["380.0000", "390.0000"]);
+        assert_eq!(
+            transport.sent.lock().unwrap().last().unwrap(),
+            "https://api.robinhood.com/options/instruments/?chain_id=regular\
+             &expiration_dates=2021-03-19&state=active&type=call"
+        );
+
+        let all = block_on(client.find_options("SPY", "2021-03-19", None, ..)).unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[0].strike_price, "370.0000");
+    }
+}
@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, OPTIONS_MARKET_DATA_PATH, ROBINHOOD_API_URL};
+
+// Robinhood rejects market data requests with too many ids
+const MAX_IDS_PER_REQUEST: usize = 40;
+
+impl Robinhood {
+    /// Calls api.robinhood.com/marketdata/options/?ids=(ids) for the option instrument `ids`
+    ///
+    /// Ids are batched into as few requests as possible. Contracts Robinhood has no
+    /// market data for are left out of the result.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::OptionType;
+    ///
+    /// let calls = robinhood_client
+    ///     .find_options("SPY", "2021-03-19", Some(OptionType::Call), 380.0..=400.0)
+    ///     .await?;
+    /// let ids: Vec<&str> = calls.iter().map(|c| c.id.as_str()).collect();
+    /// let mut data = robinhood_client.get_option_market_data(&ids).await?;
+    /// // Highest implied volatility first
+    /// data.sort_by(|a, b| {
+    ///     let (a, b) = (a.greeks().implied_volatility, b.greeks().implied_volatility);
+    ///     b.partial_cmp(&a).unwrap()
+    /// });
+    /// ```
+    pub async fn get_option_market_data(
+        &self,
+        ids: &[&str],
+    ) -> Result<Vec<OptionMarketData>, RobinhoodErr> {
+        let mut market_data = Vec::with_capacity(ids.len());
+        for batch in ids.chunks(MAX_IDS_PER_REQUEST) {
+            let url = &format!(
+                "{}{}?ids={}",
+                ROBINHOOD_API_URL,
+                OPTIONS_MARKET_DATA_PATH,
+                batch.join(",")
+            );
+            let response = self
+                .req(RobinhoodReq {
+                    kind: ReqKind::Get,
+                    payload: None,
+                    url,
+                    timeout: None,
+                    retry: true,
+                })
+                .await?;
+            let res = decode::<OptionMarketDataResponse>(&response)?;
+            market_data.extend(res.results.into_iter().flatten());
+        }
+        Ok(market_data)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OptionMarketDataResponse {
+    results: Vec<Option<OptionMarketData>>,
+}
+
+// "adjusted_mark_price": "4.270000",
+// "ask_price": "4.300000",
+// "ask_size": 91,
+// "bid_price": "4.240000",
+// "bid_size": 10,
+// "break_even_price": "394.270000",
+// "high_price": "5.100000",
+// "low_price": "3.820000",
+// "last_trade_price": "4.260000",
+// "last_trade_size": 2,
+// "mark_price": "4.270000",
+// "open_interest": 18204,
+// "previous_close_date": "2021-03-03",
+// "previous_close_price": "5.540000",
+// "volume": 9841,
+// "symbol": "SPY",
+// "occ_symbol": "SPY   210319C00390000",
+// "chance_of_profit_long": "0.281234",
+// "chance_of_profit_short": "0.718766",
+// "delta": "0.386514",
+// "gamma": "0.031203",
+// "implied_volatility": "0.214337",
+// "rho": "0.049412",
+// "theta": "-0.201538",
+// "vega": "0.331024",
+// "updated_at": "2021-03-04T21:00:00.104862Z",
+// "instrument": "https://api.robinhood.com/options/instruments/e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e/",
+// "instrument_id": "e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct OptionMarketData {
+    pub adjusted_mark_price: String,
+    pub ask_price: String,
+    pub ask_size: usize,
+    pub bid_price: String,
+    pub bid_size: usize,
+    pub break_even_price: String,
+    pub high_price: Option<String>,
+    pub low_price: Option<String>,
+    pub last_trade_price: Option<String>,
+    pub last_trade_size: Option<usize>,
+    pub mark_price: String,
+    pub open_interest: usize,
+    pub previous_close_date: String,
+    pub previous_close_price: String,
+    pub volume: usize,
+    pub symbol: String,
+    pub occ_symbol: String,
+    pub chance_of_profit_long: Option<String>,
+    pub chance_of_profit_short: Option<String>,
+    pub delta: Option<String>,
+    pub gamma: Option<String>,
+    pub implied_volatility: Option<String>,
+    pub rho: Option<String>,
+    pub theta: Option<String>,
+    pub vega: Option<String>,
+    pub updated_at: String,
+    pub instrument: String,
+    pub instrument_id: String,
+}
+
+/// The greeks of an `OptionMarketData` parsed as numbers
+///
+/// Values are `None` when Robinhood could not compute them, usually for contracts without quotes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Greeks {
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub theta: Option<f64>,
+    pub vega: Option<f64>,
+    pub rho: Option<f64>,
+    pub implied_volatility: Option<f64>,
+}
+
+fn parse(value: &Option<String>) -> Option<f64> {
+    value.as_ref().and_then(|v| v.parse::<f64>().ok())
+}
+
+impl OptionMarketData {
+    pub fn greeks(&self) -> Greeks {
+        Greeks {
+            delta: parse(&self.delta),
+            gamma: parse(&self.gamma),
+            theta: parse(&self.theta),
+            vega: parse(&self.vega),
+            rho: parse(&self.rho),
+            implied_volatility: parse(&self.implied_volatility),
+        }
+    }
+
+    /// `mark_price` as a number
+    pub fn mark(&self) -> Result<f64, RobinhoodErr> {
+        Ok(self.mark_price.parse::<f64>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    use crate::{HttpRequest, HttpResponse, HttpTransport, RateLimits};
+
+    // Answers with market data for every requested id, except null for the ones
+    // starting with "expired", and keeps the requested URLs
+    #[derive(Default)]
+    struct MarketData {
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl HttpTransport for MarketData {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            self.sent.lock().unwrap().push(request.url.clone());
+            let ids = request.url.split("?ids=").nth(1).unwrap_or_default();
+            let results: Vec<Option<OptionMarketData>> = ids
+                .split(',')
+                .map(|id| {
+                    (!id.starts_with("expired")).then(|| OptionMarketData {
+                        instrument_id: id.to_owned(),
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: json!({ "results": results }).to_string(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn batches_ids_and_skips_missing_market_data() {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        let transport = Arc::new(MarketData::default());
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+
+        let mut ids: Vec<String> = (0..85).map(|i| format!("option{}", i)).collect();
+        ids[3] = "expired3".to_owned();
+        ids[84] = "expired84".to_owned();
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let market_data = block_on(client.get_option_market_data(&ids)).unwrap();
+
+        let batches: Vec<usize> = transport
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|url| url.split(',').count())
+            .collect();
+        assert_eq!(batches, vec![40, 40, 5]);
+        assert_eq!(market_data.len(), 83);
+        assert_eq!(market_data[3].instrument_id, "option4");
+        assert!(market_data
+            .iter()
+            .all(|data| data.instrument_id.starts_with("option")));
+    }
+}
@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::RobinhoodErr;
+use crate::options::OptionInstrument;
+use crate::orders::{Execution, OrderState, OrderType, RefIdOrder, Side, TimeInForce, Trigger};
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, OPTIONS_ORDERS_PATH, ROBINHOOD_API_URL};
+
+// Shares per contract, used to turn a per-share premium into a notional
+const CONTRACT_MULTIPLIER: f64 = 100.0;
+const MAX_LEGS: usize = 4;
+
+impl Robinhood {
+    /// Submits a one to four leg option order to api.robinhood.com/options/orders/
+    ///
+    /// The request is validated first, see `OptionOrderRequest::validate`. Submissions are
+    /// deduplicated on `ref_id` and retried the same way `place_order` does. The kill switch,
+    /// symbol lists and notional limits of the `RiskPolicy` apply to the chain symbol, with
+    /// the notional being `price * quantity * 100`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{Direction, OptionLeg, OptionOrderRequest, OptionType, PositionEffect, Side};
+    ///
+    /// // Bull call spread
+    /// let calls = robinhood_client
+    ///     .find_options("SPY", "2021-03-19", Some(OptionType::Call), 385.0..=390.0)
+    ///     .await?;
+    /// let legs = vec![
+    ///     OptionLeg::new(calls[0].clone(), Side::Buy, PositionEffect::Open, 1),
+    ///     OptionLeg::new(calls[calls.len() - 1].clone(), Side::Sell, PositionEffect::Open, 1),
+    /// ];
+    /// let request = OptionOrderRequest::new(legs, Direction::Debit, 1.85, 2);
+    /// let order = robinhood_client.place_option_order(&request).await?;
+    /// ```
+    pub async fn place_option_order(
+        &self,
+        order: &OptionOrderRequest,
+    ) -> Result<OptionOrder, RobinhoodErr> {
+        order.validate()?;
+        if self.is_paper_trading() {
+            return Err(RobinhoodErr::InvalidOrder(
+                "Option orders are not supported while paper trading".to_owned(),
+            ));
+        }
+        let symbol = &order.legs[0].option.chain_symbol;
+        let notional = order.notional();
+        let reservation = self.check_basic_risk(symbol, notional)?;
+        let account = self.get_account().await?;
+        let payload = order.build_payload(&account.url);
+        let url = &format!("{}{}", ROBINHOOD_API_URL, OPTIONS_ORDERS_PATH);
+        reservation.settle(self.submit_with_ref_id(url, &payload, order.ref_id).await)
+    }
+
+    /// Looks through the option orders of the last 24 hours for one submitted with `ref_id`
+    pub async fn find_option_order_by_ref_id(
+        &self,
+        ref_id: Uuid,
+    ) -> Result<Option<OptionOrder>, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, OPTIONS_ORDERS_PATH);
+        self.find_by_ref_id(url, ref_id, Robinhood::ref_id_lookback())
+            .await
+    }
+
+    /// Calls api.robinhood.com/options/orders/ and returns every option order, newest first
+    pub async fn get_option_orders(&self) -> Result<Vec<OptionOrder>, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, OPTIONS_ORDERS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Calls api.robinhood.com/options/orders/(id)/ and returns the body as `OptionOrder`
+    pub async fn get_option_order(&self, order_id: &str) -> Result<OptionOrder, RobinhoodErr> {
+        let url = &format!("{}{}{}/", ROBINHOOD_API_URL, OPTIONS_ORDERS_PATH, order_id);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<OptionOrder>(&response)
+    }
+
+    /// Requests the cancellation of an open option order
+    pub async fn cancel_option_order(&self, order_id: &str) -> Result<(), RobinhoodErr> {
+        if self.is_paper_trading() {
+            return Err(RobinhoodErr::InvalidOrder(
+                "Option orders are not supported while paper trading".to_owned(),
+            ));
+        }
+        let url = &format!(
+            "{}{}{}/cancel/",
+            ROBINHOOD_API_URL, OPTIONS_ORDERS_PATH, order_id
+        );
+        self.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            payload: None,
+            url,
+            timeout: None,
+            retry: false,
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Net premium is paid
+    Debit,
+    /// Net premium is received
+    Credit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionEffect {
+    Open,
+    Close,
+}
+
+/// One leg of an `OptionOrderRequest`
+#[derive(Debug, Clone)]
+pub struct OptionLeg {
+    pub option: OptionInstrument,
+    pub side: Side,
+    pub position_effect: PositionEffect,
+    /// Contracts of this leg per unit of the order's `quantity`
+    pub ratio_quantity: u32,
+}
+
+impl OptionLeg {
+    pub fn new(
+        option: OptionInstrument,
+        side: Side,
+        position_effect: PositionEffect,
+        ratio_quantity: u32,
+    ) -> Self {
+        OptionLeg {
+            option,
+            side,
+            position_effect,
+            ratio_quantity,
+        }
+    }
+}
+
+/// A limit option order of one to four legs
+#[derive(Debug, Clone)]
+pub struct OptionOrderRequest {
+    pub legs: Vec<OptionLeg>,
+    pub direction: Direction,
+    /// Net debit or credit per unit of `quantity`, per share
+    pub price: f64,
+    pub quantity: u32,
+    pub time_in_force: TimeInForce,
+    /// Allow legs with different expirations (calendar and diagonal spreads).
+    /// Default is `false`
+    pub allow_calendar: bool,
+    pub ref_id: Uuid,
+}
+
+impl OptionOrderRequest {
+    /// A limit order good for the day with a fresh `ref_id`
+    pub fn new(legs: Vec<OptionLeg>, direction: Direction, price: f64, quantity: u32) -> Self {
+        OptionOrderRequest {
+            legs,
+            direction,
+            price,
+            quantity,
+            time_in_force: TimeInForce::Gfd,
+            allow_calendar: false,
+            ref_id: Uuid::new_v4(),
+        }
+    }
+
+    /// Checks the order can be sent to Robinhood
+    ///
+    /// Rejects orders without legs or with more than four, zero ratios or quantities,
+    /// non positive prices, legs on different underlyings, the same contract on
+    /// several legs, and legs with different expirations unless `allow_calendar` is set.
+    pub fn validate(&self) -> Result<(), RobinhoodErr> {
+        let invalid = |msg: String| Err(RobinhoodErr::InvalidOrder(msg));
+        if self.legs.is_empty() || self.legs.len() > MAX_LEGS {
+            return invalid(format!(
+                "Option orders need 1 to {} legs, got {}",
+                MAX_LEGS,
+                self.legs.len()
+            ));
+        }
+        if self.quantity == 0 {
+            return invalid("Quantity must be at least 1".to_owned());
+        }
+        if self.price.is_nan() || self.price <= 0.0 {
+            return invalid(format!("Price must be positive, got {}", self.price));
+        }
+        let first = &self.legs[0].option;
+        for (i, leg) in self.legs.iter().enumerate() {
+            if leg.ratio_quantity == 0 {
+                return invalid(format!("Leg {} has a ratio quantity of 0", i + 1));
+            }
+            if leg.option.chain_id != first.chain_id {
+                return invalid(format!(
+                    "Leg {} is on {} while leg 1 is on {}",
+                    i + 1,
+                    leg.option.chain_symbol,
+                    first.chain_symbol
+                ));
+            }
+            if !self.allow_calendar && leg.option.expiration_date != first.expiration_date {
+                return invalid(format!(
+                    "Leg {} expires on {} while leg 1 expires on {}, set allow_calendar for calendar spreads",
+                    i + 1,
+                    leg.option.expiration_date,
+                    first.expiration_date
+                ));
+            }
+            if self.legs[..i].iter().any(|l| l.option.id == leg.option.id) {
+                return invalid(format!("Leg {} repeats the contract of another leg", i + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Premium paid or received for the whole order
+    pub fn notional(&self) -> f64 {
+        self.price * self.quantity as f64 * CONTRACT_MULTIPLIER
+    }
+
+    fn build_payload(&self, account_url: &str) -> Value {
+        let legs: Vec<Value> = self
+            .legs
+            .iter()
+            .map(|leg| {
+                json!({
+                    "option": leg.option.url,
+                    "side": leg.side,
+                    "position_effect": leg.position_effect,
+                    "ratio_quantity": leg.ratio_quantity,
+                })
+            })
+            .collect();
+        json!({
+            "account": account_url,
+            "direction": self.direction,
+            "legs": legs,
+            "type": OrderType::Limit,
+            "trigger": Trigger::Immediate,
+            "time_in_force": self.time_in_force,
+            "price": format!("{:.2}", self.price),
+            "quantity": self.quantity.to_string(),
+            "ref_id": self.ref_id,
+            "override_day_trade_checks": false,
+            "override_dtbp_checks": false,
+        })
+    }
+}
+
+// "id": "4c2d3e1f-0a9b-4c8d-7e6f-5a4b3c2d1e0f",
+// "option": "https://api.robinhood.com/options/instruments/e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e/",
+// "position_effect": "open",
+// "ratio_quantity": 1,
+// "side": "buy",
+// "executions": []
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptionOrderLeg {
+    pub id: String,
+    pub option: String,
+    pub position_effect: PositionEffect,
+    pub ratio_quantity: u32,
+    pub side: Side,
+    pub executions: Vec<Execution>,
+}
+
+// "id": "6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a",
+// "ref_id": "0a9d8c7b-6e5f-4a3b-2c1d-0e9f8a7b6c5d",
+// "chain_id": "c277b118-58d9-4060-8dc5-a3b5898955cb",
+// "chain_symbol": "SPY",
+// "cancel_url": "https://api.robinhood.com/options/orders/6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a/cancel/",
+// "canceled_quantity": "0.00000",
+// "direction": "debit",
+// "legs": [{...}],
+// "opening_strategy": "long_call_spread",
+// "closing_strategy": null,
+// "pending_quantity": "2.00000",
+// "premium": "185.00000000",
+// "processed_premium": "0.00000000",
+// "price": "1.85000000",
+// "processed_quantity": "0.00000",
+// "quantity": "2.00000",
+// "state": "confirmed",
+// "time_in_force": "gfd",
+// "trigger": "immediate",
+// "type": "limit",
+// "created_at": "2021-03-04T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptionOrder {
+    pub id: String,
+    pub ref_id: Option<Uuid>,
+    pub chain_id: String,
+    pub chain_symbol: String,
+    pub cancel_url: Option<String>,
+    pub canceled_quantity: String,
+    pub direction: Direction,
+    pub legs: Vec<OptionOrderLeg>,
+    pub opening_strategy: Option<String>,
+    pub closing_strategy: Option<String>,
+    pub pending_quantity: String,
+    pub premium: Option<String>,
+    pub processed_premium: String,
+    pub price: Option<String>,
+    pub processed_quantity: String,
+    pub quantity: String,
+    pub state: OrderState,
+    pub time_in_force: TimeInForce,
+    pub trigger: Trigger,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl RefIdOrder for OptionOrder {
+    fn ref_id(&self) -> Option<Uuid> {
+        self.ref_id
+    }
+
+    fn created_at(&self) -> &str {
+        &self.created_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{MinTicks, OptionType};
+
+    fn option(id: &str, chain: &str, expiration: &str) -> OptionInstrument {
+        OptionInstrument {
+            id: id.to_owned(),
+            url: format!("https://api.robinhood.com/options/instruments/{}/", id),
+            chain_id: chain.to_owned(),
+            chain_symbol: chain.to_uppercase(),
+            option_type: OptionType::Call,
+            strike_price: "390.0000".to_owned(),
+            expiration_date: expiration.to_owned(),
+            issue_date: String::new(),
+            state: "active".to_owned(),
+            tradability: "tradable".to_owned(),
+            rhs_tradability: "tradable".to_owned(),
+            min_ticks: MinTicks::default(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn leg(option: OptionInstrument, side: Side) -> OptionLeg {
+        OptionLeg::new(option, side, PositionEffect::Open, 1)
+    }
+
+    #[test]
+    fn validates_legs() {
+        let spread = OptionOrderRequest::new(
+            vec![
+                leg(option("a", "spy", "2021-03-19"), Side::Buy),
+                leg(option("b", "spy", "2021-03-19"), Side::Sell),
+            ],
+            Direction::Debit,
+            1.85,
+            2,
+        );
+        assert!(spread.validate().is_ok());
+        assert_eq!(spread.notional(), 370.0);
+        let payload = spread.build_payload("account_url");
+        assert_eq!(payload["legs"][1]["side"], "sell");
+        assert_eq!(payload["price"], "1.85");
+
+        let mut calendar = spread.clone();
+        calendar.legs[1] = leg(option("b", "spy", "2021-04-16"), Side::Sell);
+        assert!(calendar.validate().is_err());
+        calendar.allow_calendar = true;
+        assert!(calendar.validate().is_ok());
+
+        let mut mixed = spread.clone();
+        mixed.legs[1] = leg(option("c", "qqq", "2021-03-19"), Side::Sell);
+        assert!(mixed.validate().is_err());
+
+        let mut too_many = spread;
+        for id in &["c", "d", "e"] {
+            too_many
+                .legs
+                .push(leg(option(id, "spy", "2021-03-19"), Side::Buy));
+        }
+        assert!(too_many.validate().is_err());
+    }
+
+    #[test]
+    fn paper_trading_never_cancels_live_orders() {
+        let mut client = futures::executor::block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        client.enable_paper_trading(10_000.0);
+        let cancelled = futures::executor::block_on(client.cancel_option_order("6a3e1a3e"));
+        assert!(matches!(cancelled, Err(RobinhoodErr::InvalidOrder(_))));
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::options::{OptionMarketData, OptionType};
+use crate::{
+    Robinhood, OPTIONS_AGGREGATE_POSITIONS_PATH, OPTIONS_POSITIONS_PATH, ROBINHOOD_API_URL,
+};
+
+impl Robinhood {
+    /// Calls api.robinhood.com/options/positions/?nonzero=true and returns every open option leg
+    pub async fn get_option_positions(&self) -> Result<Vec<OptionPosition>, RobinhoodErr> {
+        let url = &format!(
+            "{}{}?nonzero=true",
+            ROBINHOOD_API_URL, OPTIONS_POSITIONS_PATH
+        );
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Calls api.robinhood.com/options/aggregate_positions/?nonzero=true and returns the open
+    /// positions grouped the way Robinhood groups them into strategies
+    pub async fn get_aggregate_option_positions(
+        &self,
+    ) -> Result<Vec<AggregateOptionPosition>, RobinhoodErr> {
+        let url = &format!(
+            "{}{}?nonzero=true",
+            ROBINHOOD_API_URL, OPTIONS_AGGREGATE_POSITIONS_PATH
+        );
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Every open option leg joined with its current market data in a single batched call
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let positions = robinhood_client.get_valued_option_positions().await?;
+    /// let exposure = robinhood::OptionExposure::of(&positions);
+    /// println!("delta {:.0} theta {:.2}/day", exposure.delta, exposure.theta);
+    /// ```
+    pub async fn get_valued_option_positions(
+        &self,
+    ) -> Result<Vec<ValuedOptionPosition>, RobinhoodErr> {
+        let positions = self.get_option_positions().await?;
+        let ids: Vec<&str> = positions.iter().map(|p| p.option_id.as_str()).collect();
+        let mut market_data: HashMap<String, OptionMarketData> = self
+            .get_option_market_data(&ids)
+            .await?
+            .into_iter()
+            .map(|data| (data.instrument_id.clone(), data))
+            .collect();
+        Ok(positions
+            .into_iter()
+            .map(|position| ValuedOptionPosition {
+                market_data: market_data.remove(&position.option_id),
+                position,
+            })
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionPositionType {
+    Long,
+    Short,
+}
+
+// "id": "9f8e7d6c-5b4a-4392-8170-6f5e4d3c2b1a",
+// "url": "https://api.robinhood.com/options/positions/9f8e7d6c-5b4a-4392-8170-6f5e4d3c2b1a/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "chain_id": "c277b118-58d9-4060-8dc5-a3b5898955cb",
+// "chain_symbol": "SPY",
+// "option": "https://api.robinhood.com/options/instruments/e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e/",
+// "option_id": "e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e",
+// "type": "long",
+// "quantity": "2.0000",
+// "average_price": "185.0000",
+// "intraday_quantity": "0.0000",
+// "intraday_average_open_price": "0.0000",
+// "pending_buy_quantity": "0.0000",
+// "pending_sell_quantity": "0.0000",
+// "trade_value_multiplier": "100.0000",
+// "created_at": "2021-03-04T15:03:40.211000Z",
+// "updated_at": "2021-03-04T15:03:40.211000Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptionPosition {
+    pub id: String,
+    pub url: String,
+    pub account: String,
+    pub chain_id: String,
+    pub chain_symbol: String,
+    pub option: String,
+    pub option_id: String,
+    #[serde(rename = "type")]
+    pub position_type: OptionPositionType,
+    pub quantity: String,
+    /// Average price paid per contract, already multiplied by `trade_value_multiplier`
+    pub average_price: String,
+    pub intraday_quantity: String,
+    pub intraday_average_open_price: String,
+    pub pending_buy_quantity: String,
+    pub pending_sell_quantity: String,
+    pub trade_value_multiplier: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// "id": "4a1f5e5c-0c4c-4c64-9f3e-2d1c0b9a8f7e",
+// "position": "https://api.robinhood.com/options/positions/9f8e7d6c-5b4a-4392-8170-6f5e4d3c2b1a/",
+// "position_type": "long",
+// "option": "https://api.robinhood.com/options/instruments/e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e/",
+// "option_id": "e4e27a2e-1b8c-4c0e-9d6e-3f3a4c2b1d0e",
+// "ratio_quantity": 1,
+// "expiration_date": "2021-03-19",
+// "strike_price": "385.0000",
+// "option_type": "call"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregateOptionLeg {
+    pub id: String,
+    pub position: String,
+    pub position_type: OptionPositionType,
+    pub option: String,
+    pub option_id: String,
+    pub ratio_quantity: u32,
+    pub expiration_date: String,
+    pub strike_price: String,
+    pub option_type: OptionType,
+}
+
+// "id": "0b2e3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d",
+// "chain": "https://api.robinhood.com/options/chains/c277b118-58d9-4060-8dc5-a3b5898955cb/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "symbol": "SPY",
+// "strategy": "long_call_spread",
+// "direction": "debit",
+// "average_open_price": "185.0000",
+// "quantity": "2.0000",
+// "intraday_quantity": "0.0000",
+// "intraday_average_open_price": "0.0000",
+// "trade_value_multiplier": "100.0000",
+// "legs": [{...}],
+// "created_at": "2021-03-04T15:03:40.211000Z",
+// "updated_at": "2021-03-04T15:03:40.211000Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregateOptionPosition {
+    pub id: String,
+    pub chain: String,
+    pub account: String,
+    pub symbol: String,
+    /// Robinhood's name for the strategy, e.g. "long_call_spread" or "iron_condor"
+    pub strategy: String,
+    pub direction: String,
+    pub average_open_price: String,
+    pub quantity: String,
+    pub intraday_quantity: String,
+    pub intraday_average_open_price: String,
+    pub trade_value_multiplier: String,
+    pub legs: Vec<AggregateOptionLeg>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// An option leg with the market data of its contract, `None` when Robinhood has none
+#[derive(Debug, Clone)]
+pub struct ValuedOptionPosition {
+    pub position: OptionPosition,
+    pub market_data: Option<OptionMarketData>,
+}
+
+impl ValuedOptionPosition {
+    // Signed number of contracts times the multiplier: the number of shares the leg controls
+    fn signed_shares(&self) -> f64 {
+        let quantity = self.position.quantity.parse::<f64>().unwrap_or_default();
+        let multiplier = self
+            .position
+            .trade_value_multiplier
+            .parse::<f64>()
+            .unwrap_or(100.0);
+        match self.position.position_type {
+            OptionPositionType::Long => quantity * multiplier,
+            OptionPositionType::Short => -quantity * multiplier,
+        }
+    }
+
+    /// Current value of the leg at the mark price, negative for short legs
+    pub fn market_value(&self) -> Option<f64> {
+        let mark = self.market_data.as_ref()?.mark().ok()?;
+        Some(mark * self.signed_shares())
+    }
+}
+
+/// Portfolio level greeks of a set of option legs
+///
+/// `delta` is expressed in shares of the underlying and `gamma`, `theta`, `vega` and `rho`
+/// in dollars, all signed with long legs adding and short legs subtracting.
+/// Legs without market data or greeks are skipped and counted in `missing`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OptionExposure {
+    pub market_value: f64,
+    pub delta: f64,
+    pub gamma: f64,
+    pub theta: f64,
+    pub vega: f64,
+    pub rho: f64,
+    pub missing: usize,
+}
+
+impl OptionExposure {
+    pub fn of(positions: &[ValuedOptionPosition]) -> Self {
+        let mut exposure = OptionExposure::default();
+        for position in positions {
+            let data = match &position.market_data {
+                Some(data) => data,
+                None => {
+                    exposure.missing += 1;
+                    continue;
+                }
+            };
+            let greeks = data.greeks();
+            let (delta, gamma, theta, vega, rho, mark) = match (
+                greeks.delta,
+                greeks.gamma,
+                greeks.theta,
+                greeks.vega,
+                greeks.rho,
+                data.mark(),
+            ) {
+                (Some(d), Some(g), Some(t), Some(v), Some(r), Ok(m)) => (d, g, t, v, r, m),
+                _ => {
+                    exposure.missing += 1;
+                    continue;
+                }
+            };
+            let shares = position.signed_shares();
+            exposure.market_value += mark * shares;
+            exposure.delta += delta * shares;
+            exposure.gamma += gamma * shares;
+            exposure.theta += theta * shares;
+            exposure.vega += vega * shares;
+            exposure.rho += rho * shares;
+        }
+        exposure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valued(
+        position_type: OptionPositionType,
+        quantity: &str,
+        delta: &str,
+    ) -> ValuedOptionPosition {
+        ValuedOptionPosition {
+            position: OptionPosition {
+                id: String::new(),
+                url: String::new(),
+                account: String::new(),
+                chain_id: String::new(),
+                chain_symbol: "SPY".to_owned(),
+                option: String::new(),
+                option_id: String::new(),
+                position_type,
+                quantity: quantity.to_owned(),
+                average_price: "100.0000".to_owned(),
+                intraday_quantity: "0.0000".to_owned(),
+                intraday_average_open_price: "0.0000".to_owned(),
+                pending_buy_quantity: "0.0000".to_owned(),
+                pending_sell_quantity: "0.0000".to_owned(),
+                trade_value_multiplier: "100.0000".to_owned(),
+                created_at: String::new(),
+                updated_at: String::new(),
+            },
+            market_data: Some(OptionMarketData {
+                mark_price: "2.000000".to_owned(),
+                delta: Some(delta.to_owned()),
+                gamma: Some("0.010000".to_owned()),
+                theta: Some("-0.050000".to_owned()),
+                vega: Some("0.100000".to_owned()),
+                rho: Some("0.020000".to_owned()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn sums_signed_exposure() {
+        let mut no_data = valued(OptionPositionType::Long, "1.0000", "0.5");
+        no_data.market_data = None;
+        let positions = vec![
+            valued(OptionPositionType::Long, "2.0000", "0.600000"),
+            valued(OptionPositionType::Short, "1.0000", "0.400000"),
+            no_data,
+        ];
+        let exposure = OptionExposure::of(&positions);
+        assert!((exposure.delta - 80.0).abs() < 1e-9);
+        assert!((exposure.theta + 5.0).abs() < 1e-9);
+        assert!((exposure.market_value - 200.0).abs() < 1e-9);
+        assert_eq!(exposure.missing, 1);
+        assert_eq!(positions[1].market_value(), Some(-200.0));
+    }
+}
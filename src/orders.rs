@@ -0,0 +1,594 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::datetime::{format_timestamp, now_secs, parse_timestamp};
+use crate::error::RobinhoodErr;
+use crate::queries::QuotesResponse;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, ORDERS_PATH, ROBINHOOD_API_URL};
+
+// An order submission taking longer than this is treated as a dropped connection
+const ORDER_SUBMIT_TIMEOUT: Duration = Duration::from_secs(10);
+// Number of times the same ref_id is submitted before giving up
+const ORDER_SUBMIT_ATTEMPTS: usize = 3;
+// Orders are looked up from this long before the submission, for clock skew
+const ORDER_LOOKUP_SLACK_SECS: i64 = 300;
+// How far back `find_order_by_ref_id` and co look
+const REF_ID_LOOKBACK_SECS: i64 = 86400;
+
+impl Robinhood {
+    /// Submits an order to api.robinhood.com/orders/
+    ///
+    /// The request's `ref_id` is always sent along with the order. Robinhood uses it to
+    /// deduplicate submissions so the same `OrderRequest` can never create two orders.
+    ///
+    /// If the submission times out, the connection drops or Robinhood answers with a 5xx,
+    /// the order may or may not have been created. In that case the client looks up
+    /// an existing order with the same `ref_id` and returns it instead of resubmitting.
+    /// Only when no such order exists is the submission retried. A successful response
+    /// with an unreadable body is never retried, `RobinhoodErr::Decode` then means the
+    /// order was created but couldn't be found yet.
+    ///
+    /// Orders are checked against the kill switch and the `RiskPolicy` of the client
+    /// before anything is submitted, violations return `RobinhoodErr::RiskRejected`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{OrderRequest, Side};
+    ///
+    /// let request = OrderRequest::limit("SPY", Side::Buy, 1.0, 380.0);
+    /// // Keep the ref_id around to find the order later on
+    /// let ref_id = request.ref_id;
+    /// let order = robinhood_client.place_order(&request).await?;
+    /// assert_eq!(order.ref_id, Some(ref_id));
+    /// ```
+    pub async fn place_order(&self, order: &OrderRequest) -> Result<Order, RobinhoodErr> {
+        let quote = self.get_quote(&order.symbol).await?;
+        let reservation = self.check_risk(order, &quote).await?;
+        let account = self.get_account().await?;
+        let placed = match &self.paper {
+            Some(paper) => paper.submit(order, &quote, &account.url),
+            None => {
+                let payload = order.build_payload(&account.url, &quote)?;
+                let url = &format!("{}{}", ROBINHOOD_API_URL, ORDERS_PATH);
+                self.submit_with_ref_id(url, &payload, order.ref_id).await
+            }
+        };
+        reservation.settle(placed)
+    }
+
+    /// Posts `payload` to `url` and retries ambiguous failures without ever creating
+    /// a second order, see `place_order`
+    pub(crate) async fn submit_with_ref_id<T: RefIdOrder>(
+        &self,
+        url: &str,
+        payload: &Value,
+        ref_id: Uuid,
+    ) -> Result<T, RobinhoodErr> {
+        let since = now_secs() as i64 - ORDER_LOOKUP_SLACK_SECS;
+        let mut attempt = 1;
+        loop {
+            let err = match self
+                .req(RobinhoodReq {
+                    kind: ReqKind::Post,
+                    payload: Some(payload),
+                    url,
+                    timeout: Some(ORDER_SUBMIT_TIMEOUT),
+                    retry: false,
+                })
+                .await
+            {
+                Ok(res) => match decode::<T>(&res) {
+                    Ok(order) => return Ok(order),
+                    // The order was created even if its body can't be read, it is looked
+                    // up but never submitted again
+                    Err(e) => {
+                        return match self.find_by_ref_id::<T>(url, ref_id, since).await? {
+                            Some(order) => Ok(order),
+                            None => Err(e),
+                        }
+                    }
+                },
+                Err(e) if e.is_ambiguous() => e,
+                // Robinhood rejected the order outright, nothing was created
+                Err(e) => return Err(e),
+            };
+            // The order may have reached Robinhood before the failure.
+            // Never resubmit without checking for it first
+            if let Some(order) = self.find_by_ref_id::<T>(url, ref_id, since).await? {
+                return Ok(order);
+            }
+            if attempt >= ORDER_SUBMIT_ATTEMPTS {
+                return Err(err);
+            }
+            attempt += 1;
+        }
+    }
+
+    // Looks through the orders listed at `url` since `since`, in seconds since the epoch,
+    // for one submitted with `ref_id`. Every page is read, a busy day spans many
+    pub(crate) async fn find_by_ref_id<T: RefIdOrder>(
+        &self,
+        url: &str,
+        ref_id: Uuid,
+        since: i64,
+    ) -> Result<Option<T>, RobinhoodErr> {
+        let url = &format!("{}?updated_at%5Bgte%5D={}", url, format_timestamp(since));
+        let mut orders = self.paginate::<T>(url);
+        while let Some(order) = orders.next().await {
+            let order = order?;
+            if order.ref_id() == Some(ref_id) {
+                return Ok(Some(order));
+            }
+            // Orders are listed newest first, the rest were created before `since`
+            if parse_timestamp(order.created_at()).is_some_and(|created| created < since) {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    // Start of the window searched by the public ref_id lookups
+    pub(crate) fn ref_id_lookback() -> i64 {
+        now_secs() as i64 - REF_ID_LOOKBACK_SECS
+    }
+
+    /// Looks through the orders of the last 24 hours for one submitted with `ref_id`
+    pub async fn find_order_by_ref_id(&self, ref_id: Uuid) -> Result<Option<Order>, RobinhoodErr> {
+        if let Some(paper) = &self.paper {
+            return Ok(paper.find_by_ref_id(ref_id));
+        }
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ORDERS_PATH);
+        self.find_by_ref_id(url, ref_id, Robinhood::ref_id_lookback())
+            .await
+    }
+
+    /// Calls api.robinhood.com/orders/ and returns the `limit` most recent orders, newest
+    /// first, or every order when `limit` is `None`
+    pub async fn get_orders(&self, limit: Option<usize>) -> Result<Vec<Order>, RobinhoodErr> {
+        if let Some(paper) = &self.paper {
+            let orders = paper.orders().into_iter().rev();
+            return Ok(orders.take(limit.unwrap_or(usize::MAX)).collect());
+        }
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ORDERS_PATH);
+        self.paginate(url).collect_all(limit).await
+    }
+
+    /// Calls api.robinhood.com/orders/(id)/ and returns the body as `Order`
+    pub async fn get_order(&self, order_id: &str) -> Result<Order, RobinhoodErr> {
+        if let Some(paper) = &self.paper {
+            return self.get_paper_order(paper, order_id).await;
+        }
+        let url = &format!("{}{}{}/", ROBINHOOD_API_URL, ORDERS_PATH, order_id);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<Order>(&response)
+    }
+
+    /// Requests the cancellation of an open order
+    ///
+    /// Robinhood processes cancellations asynchronously, use `get_order` to follow up on the state
+    pub async fn cancel_order(&self, order_id: &str) -> Result<(), RobinhoodErr> {
+        if let Some(paper) = &self.paper {
+            return paper.cancel(order_id);
+        }
+        let url = &format!("{}{}{}/cancel/", ROBINHOOD_API_URL, ORDERS_PATH, order_id);
+        self.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            payload: None,
+            url,
+            timeout: None,
+            retry: false,
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// An order to submit through `Robinhood::place_order`
+///
+/// A fresh `ref_id` is generated by the constructors. Submitting the same request twice
+/// results in a single order on Robinhood's side.
+#[derive(Debug, Clone)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub trigger: Trigger,
+    pub time_in_force: TimeInForce,
+    pub quantity: f64,
+    /// Limit price. For market orders this acts as a price collar and defaults to
+    /// the current ask (buy) or bid (sell)
+    pub price: Option<f64>,
+    /// Only used when `trigger` is `Trigger::Stop`
+    pub stop_price: Option<f64>,
+    pub extended_hours: bool,
+    pub ref_id: Uuid,
+}
+
+impl OrderRequest {
+    /// A market order good for the day
+    pub fn market(symbol: &str, side: Side, quantity: f64) -> Self {
+        OrderRequest {
+            symbol: symbol.to_uppercase(),
+            side,
+            order_type: OrderType::Market,
+            trigger: Trigger::Immediate,
+            time_in_force: TimeInForce::Gfd,
+            quantity,
+            price: None,
+            stop_price: None,
+            extended_hours: false,
+            ref_id: Uuid::new_v4(),
+        }
+    }
+
+    /// A limit order good for the day
+    pub fn limit(symbol: &str, side: Side, quantity: f64, price: f64) -> Self {
+        OrderRequest {
+            order_type: OrderType::Limit,
+            price: Some(price),
+            ..OrderRequest::market(symbol, side, quantity)
+        }
+    }
+
+    /// The limit price, or the ask (buy) / bid (sell) of `quote` when there is none
+    pub(crate) fn effective_price(&self, quote: &QuotesResponse) -> Result<f64, RobinhoodErr> {
+        match self.price {
+            Some(price) => Ok(price),
+            None => match self.side {
+                Side::Buy => Ok(quote.ask_price.parse::<f64>()?),
+                Side::Sell => Ok(quote.bid_price.parse::<f64>()?),
+            },
+        }
+    }
+
+    /// Build the orders/ payload for `account_url` using the instrument from `quote`
+    fn build_payload(
+        &self,
+        account_url: &str,
+        quote: &QuotesResponse,
+    ) -> Result<Value, RobinhoodErr> {
+        let price = self.effective_price(quote)?;
+        let mut payload = json!({
+            "account": account_url,
+            "instrument": quote.instrument,
+            "symbol": self.symbol,
+            "type": self.order_type,
+            "time_in_force": self.time_in_force,
+            "trigger": self.trigger,
+            "price": format_price(price),
+            "quantity": self.quantity.to_string(),
+            "side": self.side,
+            "ref_id": self.ref_id,
+            "extended_hours": self.extended_hours,
+        });
+        if let Trigger::Stop = self.trigger {
+            match self.stop_price {
+                Some(stop_price) => {
+                    payload["stop_price"] = Value::String(format_price(stop_price));
+                }
+                None => {
+                    return Err(RobinhoodErr::InvalidOrder(
+                        "Stop orders require a stop_price".to_owned(),
+                    ));
+                }
+            }
+        }
+        Ok(payload)
+    }
+}
+
+// Robinhood only accepts sub-penny increments for prices under $1
+fn format_price(price: f64) -> String {
+    if price < 1.0 {
+        format!("{:.4}", price)
+    } else {
+        format!("{:.2}", price)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    Market,
+    Limit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Immediate,
+    Stop,
+}
+
+/// `Gfd` good for the day, `Gtc` good till cancelled,
+/// `Ioc` immediate or cancel, `Opg` market on open
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeInForce {
+    Gfd,
+    Gtc,
+    Ioc,
+    Opg,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderState {
+    Queued,
+    Unconfirmed,
+    Confirmed,
+    PartiallyFilled,
+    Filled,
+    Rejected,
+    Cancelled,
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// An order type Robinhood deduplicates on the client supplied `ref_id`
+pub(crate) trait RefIdOrder: DeserializeOwned + Send {
+    fn ref_id(&self) -> Option<Uuid>;
+    fn created_at(&self) -> &str;
+}
+
+// "id": "6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a",
+// "ref_id": "0a9d8c7b-6e5f-4a3b-2c1d-0e9f8a7b6c5d",
+// "url": "https://api.robinhood.com/orders/6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "instrument": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "cancel": "https://api.robinhood.com/orders/6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a/cancel/",
+// "state": "confirmed",
+// "side": "buy",
+// "type": "limit",
+// "trigger": "immediate",
+// "time_in_force": "gfd",
+// "price": "380.00000000",
+// "stop_price": null,
+// "quantity": "1.00000000",
+// "cumulative_quantity": "0.00000000",
+// "average_price": null,
+// "fees": "0.00",
+// "reject_reason": null,
+// "extended_hours": false,
+// "executions": [],
+// "created_at": "2021-03-04T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z",
+// "last_transaction_at": "2021-03-04T15:02:11.482381Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Order {
+    pub id: String,
+    pub ref_id: Option<Uuid>,
+    pub url: String,
+    pub account: String,
+    pub instrument: String,
+    pub cancel: Option<String>,
+    pub state: OrderState,
+    pub side: Side,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub trigger: Trigger,
+    pub time_in_force: TimeInForce,
+    pub price: Option<String>,
+    pub stop_price: Option<String>,
+    pub quantity: String,
+    pub cumulative_quantity: String,
+    pub average_price: Option<String>,
+    pub fees: String,
+    pub reject_reason: Option<String>,
+    pub extended_hours: bool,
+    pub executions: Vec<Execution>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_transaction_at: String,
+}
+
+impl RefIdOrder for Order {
+    fn ref_id(&self) -> Option<Uuid> {
+        self.ref_id
+    }
+
+    fn created_at(&self) -> &str {
+        &self.created_at
+    }
+}
+
+// "id": "1d2c3b4a-5f6e-4d8c-9b0a-1f2e3d4c5b6a",
+// "price": "380.00000000",
+// "quantity": "1.00000000",
+// "settlement_date": "2021-03-08",
+// "timestamp": "2021-03-04T15:03:40.211000Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Execution {
+    pub id: String,
+    pub price: String,
+    pub quantity: String,
+    pub settlement_date: String,
+    pub timestamp: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use std::sync::{Arc, Mutex};
+
+    use crate::{HttpRequest, HttpResponse, HttpTransport, RateLimits};
+
+    #[test]
+    fn payload_carries_ref_id_and_collar() {
+        let quote = QuotesResponse {
+            ask_price: "394.750000".to_owned(),
+            bid_price: "371.000000".to_owned(),
+            instrument: "https://api.robinhood.com/instruments/8f92e76f/".to_owned(),
+            ..Default::default()
+        };
+        let request = OrderRequest::market("spy", Side::Buy, 2.0);
+        let payload = request.build_payload("account_url", &quote).unwrap();
+        assert_eq!(payload["ref_id"], request.ref_id.to_string());
+        assert_eq!(payload["symbol"], "SPY");
+        assert_eq!(payload["type"], "market");
+        assert_eq!(payload["price"], "394.75");
+        assert_eq!(payload["quantity"], "2");
+        assert!(payload.get("stop_price").is_none());
+    }
+
+    // Serves `pages` in turn, linking each one to the next, and keeps the requested URLs
+    struct Pages {
+        pages: Vec<Vec<Value>>,
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl HttpTransport for Pages {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(request.url.clone());
+            let page = sent.len();
+            let next = (page < self.pages.len())
+                .then(|| format!("https://api.robinhood.com/orders/?cursor={}", page + 1));
+            let body = json!({ "results": self.pages[page - 1], "next": next });
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn order(ref_id: Uuid, created_at: &str) -> Value {
+        json!({
+            "id": "6a3e1a3e", "ref_id": ref_id, "url": "", "account": "", "instrument": "",
+            "cancel": null, "state": "filled", "side": "buy", "type": "market",
+            "trigger": "immediate", "time_in_force": "gfd", "price": null,
+            "stop_price": null, "quantity": "1", "cumulative_quantity": "1",
+            "average_price": null, "fees": "0.00", "reject_reason": null,
+            "extended_hours": false, "executions": [], "created_at": created_at,
+            "updated_at": created_at, "last_transaction_at": created_at,
+        })
+    }
+
+    fn find(pages: Vec<Vec<Value>>, ref_id: Uuid, since: &str) -> (Option<Order>, Vec<String>) {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        let transport = Arc::new(Pages {
+            pages,
+            sent: Mutex::new(Vec::new()),
+        });
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let url = "https://api.robinhood.com/orders/";
+        let since = parse_timestamp(since).unwrap();
+        let found = block_on(client.find_by_ref_id::<Order>(url, ref_id, since)).unwrap();
+        let sent = transport.sent.lock().unwrap().clone();
+        (found, sent)
+    }
+
+    #[test]
+    fn ref_id_lookup_reads_every_page_since_the_submission() {
+        let ref_id = Uuid::new_v4();
+        let busy_day = vec![
+            vec![order(Uuid::new_v4(), "2021-03-04T15:09:00Z"); 3],
+            vec![
+                order(Uuid::new_v4(), "2021-03-04T15:06:00Z"),
+                order(ref_id, "2021-03-04T15:05:00Z"),
+            ],
+        ];
+        let (found, sent) = find(busy_day, ref_id, "2021-03-04T15:00:00Z");
+        assert_eq!(found.unwrap().ref_id, Some(ref_id));
+        assert_eq!(
+            sent[0],
+            "https://api.robinhood.com/orders/?updated_at%5Bgte%5D=2021-03-04T15:00:00Z"
+        );
+        assert_eq!(sent.len(), 2);
+
+        // Stops at the first order older than the submission
+        let older = vec![
+            vec![order(Uuid::new_v4(), "2021-03-04T14:59:00Z")],
+            vec![order(ref_id, "2021-03-04T14:58:00Z")],
+        ];
+        let (found, sent) = find(older, ref_id, "2021-03-04T15:00:00Z");
+        assert!(found.is_none());
+        assert_eq!(sent.len(), 1);
+    }
+
+    #[test]
+    fn sub_dollar_prices_keep_four_decimals() {
+        assert_eq!(format_price(0.12345), "0.1235");
+        assert_eq!(format_price(12.346), "12.35");
+    }
+
+    // Accepts every order with an unreadable body and lists no orders, counting POSTs
+    #[derive(Default)]
+    struct Garbled {
+        posted: Mutex<usize>,
+    }
+
+    impl HttpTransport for Garbled {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let body = if request.method == ReqKind::Post {
+                *self.posted.lock().unwrap() += 1;
+                "<html>".to_owned()
+            } else {
+                json!({ "results": [], "next": null }).to_string()
+            };
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body,
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn unreadable_submissions_are_never_resent() {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        let transport = Arc::new(Garbled::default());
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let url = "https://api.robinhood.com/orders/";
+        let submitted =
+            block_on(client.submit_with_ref_id::<Order>(url, &json!({}), Uuid::new_v4()));
+        assert!(matches!(submitted, Err(RobinhoodErr::Decode { .. })));
+        assert_eq!(*transport.posted.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::Robinhood;
+
+// "results": [...],
+// "next": "https://api.robinhood.com/orders/?cursor=cD0yMDIxLTAzLTA0",
+// "previous": null
+#[derive(Debug, Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+pub(crate) struct Page<T> {
+    pub(crate) results: Vec<T>,
+    pub(crate) next: Option<String>,
+}
+
+/// The items of a Robinhood list endpoint, fetched page by page as the stream is polled
+///
+/// Pages are requested lazily by following the `next` link of the previous one, so
+/// dropping the stream early stops any further request. The stream ends after the
+/// first error.
+pub struct Paginated<'a, T> {
+    inner: Pin<Box<dyn Stream<Item = Result<T, RobinhoodErr>> + Send + 'a>>,
+}
+
+impl<'a, T: Send + 'a> Paginated<'a, T> {
+    pub(crate) fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<T, RobinhoodErr>> + Send + 'a,
+    {
+        Paginated {
+            inner: Box::pin(stream),
+        }
+    }
+
+    /// Collects every item, stopping after `cap` items when one is given
+    ///
+    /// No page beyond the one holding the last wanted item is requested.
+    pub async fn collect_all(mut self, cap: Option<usize>) -> Result<Vec<T>, RobinhoodErr> {
+        let cap = cap.unwrap_or(usize::MAX);
+        let mut items = Vec::new();
+        while items.len() < cap {
+            match self.next().await {
+                Some(item) => items.push(item?),
+                None => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+impl<T> Stream for Paginated<'_, T> {
+    type Item = Result<T, RobinhoodErr>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+struct Cursor<T> {
+    buffered: VecDeque<T>,
+    next: Option<String>,
+}
+
+impl Robinhood {
+    /// Streams the items of any Robinhood list endpoint starting at `url`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use futures::StreamExt;
+    /// use robinhood::Order;
+    ///
+    /// let mut orders = robinhood_client.paginate::<Order>("https://api.robinhood.com/orders/");
+    /// while let Some(order) = orders.next().await {
+    ///     println!("{:?}", order?.state);
+    /// }
+    /// // Or only the 100 most recent ones
+    /// let recent = robinhood_client
+    ///     .paginate::<Order>("https://api.robinhood.com/orders/")
+    ///     .collect_all(Some(100))
+    ///     .await?;
+    /// ```
+    pub fn paginate<'a, T>(&'a self, url: &str) -> Paginated<'a, T>
+    where
+        T: DeserializeOwned + Send + 'a,
+    {
+        let cursor = Cursor {
+            buffered: VecDeque::new(),
+            next: Some(url.to_owned()),
+        };
+        Paginated::from_stream(stream::unfold(cursor, move |mut cursor| async move {
+            loop {
+                if let Some(item) = cursor.buffered.pop_front() {
+                    return Some((Ok(item), cursor));
+                }
+                let url = cursor.next.take()?;
+                match self.get_page::<T>(&url).await {
+                    Ok(page) => {
+                        cursor.buffered = page.results.into();
+                        cursor.next = page.next;
+                    }
+                    Err(e) => return Some((Err(e), cursor)),
+                }
+            }
+        }))
+    }
+
+    /// Fetches a single page of a list endpoint
+    pub(crate) async fn get_page<T: DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<Page<T>, RobinhoodErr> {
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<Page<T>>(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn collect_all_stops_at_the_cap_and_on_errors() {
+        let items = || stream::iter((0..10).map(Ok));
+        let all = block_on(Paginated::from_stream(items()).collect_all(None)).unwrap();
+        assert_eq!(all.len(), 10);
+        let capped = block_on(Paginated::from_stream(items()).collect_all(Some(3))).unwrap();
+        assert_eq!(capped, vec![0, 1, 2]);
+
+        let failing = stream::iter(vec![Ok(1), Err(RobinhoodErr::InvalidCredentials), Ok(2)]);
+        assert!(block_on(Paginated::from_stream(failing).collect_all(None)).is_err());
+    }
+}
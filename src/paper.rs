@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use uuid::Uuid;
+
+use crate::datetime::{add_weekdays, format_date, now_timestamp, today};
+use crate::error::RobinhoodErr;
+use crate::orders::{Execution, Order, OrderRequest, OrderState, OrderType, Side, Trigger};
+use crate::queries::QuotesResponse;
+use crate::{Robinhood, ORDERS_PATH, ROBINHOOD_API_URL};
+
+/// A position held by the simulated broker
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperPosition {
+    pub symbol: String,
+    pub instrument: String,
+    pub quantity: f64,
+    pub average_buy_price: f64,
+}
+
+/// Snapshot of the simulated cash and positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperAccount {
+    pub cash: f64,
+    pub positions: Vec<PaperPosition>,
+}
+
+struct PaperOrder {
+    symbol: String,
+    order: Order,
+}
+
+struct PaperState {
+    cash: f64,
+    positions: HashMap<String, PaperPosition>,
+    orders: Vec<PaperOrder>,
+}
+
+/// In-memory broker the client routes orders to while paper trading
+pub(crate) struct PaperBroker {
+    state: Mutex<PaperState>,
+}
+
+fn is_open(state: OrderState) -> bool {
+    matches!(
+        state,
+        OrderState::Queued
+            | OrderState::Unconfirmed
+            | OrderState::Confirmed
+            | OrderState::PartiallyFilled
+    )
+}
+
+fn parse_or_zero(value: &str) -> f64 {
+    value.parse::<f64>().unwrap_or_default()
+}
+
+impl PaperState {
+    // Price the order would execute at right now, `None` while it should keep resting
+    fn fill_price(order: &Order, quote: &QuotesResponse) -> Option<f64> {
+        let ask = parse_or_zero(&quote.ask_price);
+        let bid = parse_or_zero(&quote.bid_price);
+        let price = match order.side {
+            Side::Buy => ask,
+            Side::Sell => bid,
+        };
+        if price <= 0.0 || quote.trading_halted {
+            return None;
+        }
+        if let (Trigger::Stop, Some(stop)) = (order.trigger, &order.stop_price) {
+            let stop = parse_or_zero(stop);
+            let triggered = match order.side {
+                Side::Buy => price >= stop,
+                Side::Sell => price <= stop,
+            };
+            if !triggered {
+                return None;
+            }
+        }
+        if let (OrderType::Limit, Some(limit)) = (order.order_type, &order.price) {
+            let limit = parse_or_zero(limit);
+            let crossed = match order.side {
+                Side::Buy => price <= limit,
+                Side::Sell => price >= limit,
+            };
+            if !crossed {
+                return None;
+            }
+        }
+        Some(price)
+    }
+
+    fn try_fill(&mut self, index: usize, quote: &QuotesResponse) {
+        let paper_order = &self.orders[index];
+        if !is_open(paper_order.order.state) {
+            return;
+        }
+        let price = match PaperState::fill_price(&paper_order.order, quote) {
+            Some(price) => price,
+            None => return,
+        };
+        let symbol = paper_order.symbol.clone();
+        let instrument = paper_order.order.instrument.clone();
+        let side = paper_order.order.side;
+        let quantity = parse_or_zero(&paper_order.order.quantity);
+        let notional = quantity * price;
+
+        let reject_reason = match side {
+            Side::Buy if notional > self.cash => Some("Insufficient buying power"),
+            Side::Sell
+                if self.positions.get(&instrument).map_or(0.0, |p| p.quantity) < quantity =>
+            {
+                Some("Not enough shares to sell")
+            }
+            _ => None,
+        };
+        let timestamp = now_timestamp();
+        let order = &mut self.orders[index].order;
+        order.updated_at = timestamp.clone();
+        order.last_transaction_at = timestamp.clone();
+        order.cancel = None;
+        if let Some(reason) = reject_reason {
+            order.state = OrderState::Rejected;
+            order.reject_reason = Some(reason.to_owned());
+            return;
+        }
+        order.state = OrderState::Filled;
+        order.cumulative_quantity = format!("{:.8}", quantity);
+        order.average_price = Some(format!("{:.8}", price));
+        order.executions.push(Execution {
+            id: Uuid::new_v4().to_string(),
+            price: format!("{:.8}", price),
+            quantity: format!("{:.8}", quantity),
+            settlement_date: format_date(add_weekdays(today(), 2)),
+            timestamp,
+        });
+
+        match side {
+            Side::Buy => {
+                self.cash -= notional;
+                let position = self
+                    .positions
+                    .entry(instrument.clone())
+                    .or_insert(PaperPosition {
+                        symbol,
+                        instrument,
+                        quantity: 0.0,
+                        average_buy_price: 0.0,
+                    });
+                let cost = position.quantity * position.average_buy_price + notional;
+                position.quantity += quantity;
+                position.average_buy_price = cost / position.quantity;
+            }
+            Side::Sell => {
+                self.cash += notional;
+                if let Some(position) = self.positions.get_mut(&instrument) {
+                    position.quantity -= quantity;
+                    if position.quantity <= 0.0 {
+                        self.positions.remove(&instrument);
+                    }
+                }
+            }
+        }
+    }
+
+    fn index_of(&self, order_id: &str) -> Option<usize> {
+        self.orders.iter().position(|o| o.order.id == order_id)
+    }
+}
+
+impl PaperBroker {
+    pub(crate) fn new(cash: f64) -> Self {
+        PaperBroker {
+            state: Mutex::new(PaperState {
+                cash,
+                positions: HashMap::new(),
+                orders: Vec::new(),
+            }),
+        }
+    }
+
+    /// Accepts `request` and fills it right away when the quote allows it
+    pub(crate) fn submit(
+        &self,
+        request: &OrderRequest,
+        quote: &QuotesResponse,
+        account_url: &str,
+    ) -> Result<Order, RobinhoodErr> {
+        let mut state = self.state.lock().unwrap();
+        // Same deduplication Robinhood applies to live orders
+        if let Some(existing) = state
+            .orders
+            .iter()
+            .find(|o| o.order.ref_id == Some(request.ref_id))
+        {
+            return Ok(existing.order.clone());
+        }
+        let id = Uuid::new_v4().to_string();
+        let url = format!("{}{}{}/", ROBINHOOD_API_URL, ORDERS_PATH, id);
+        let timestamp = now_timestamp();
+        let stop_price = match request.trigger {
+            Trigger::Stop => request.stop_price.map(|p| format!("{:.8}", p)),
+            Trigger::Immediate => None,
+        };
+        let order = Order {
+            cancel: Some(format!("{}cancel/", url)),
+            id,
+            ref_id: Some(request.ref_id),
+            url,
+            account: account_url.to_owned(),
+            instrument: quote.instrument.clone(),
+            state: OrderState::Confirmed,
+            side: request.side,
+            order_type: request.order_type,
+            trigger: request.trigger,
+            time_in_force: request.time_in_force,
+            price: Some(format!("{:.8}", request.effective_price(quote)?)),
+            stop_price,
+            quantity: format!("{:.8}", request.quantity),
+            cumulative_quantity: format!("{:.8}", 0.0),
+            average_price: None,
+            fees: "0.00".to_owned(),
+            reject_reason: None,
+            extended_hours: request.extended_hours,
+            executions: Vec::new(),
+            created_at: timestamp.clone(),
+            updated_at: timestamp.clone(),
+            last_transaction_at: timestamp,
+        };
+        state.orders.push(PaperOrder {
+            symbol: request.symbol.clone(),
+            order,
+        });
+        let index = state.orders.len() - 1;
+        state.try_fill(index, quote);
+        Ok(state.orders[index].order.clone())
+    }
+
+    pub(crate) fn symbol_of(&self, order_id: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .index_of(order_id)
+            .map(|i| state.orders[i].symbol.clone())
+    }
+
+    /// Re-evaluates a resting order against `quote`
+    pub(crate) fn update(&self, order_id: &str, quote: &QuotesResponse) -> Option<Order> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.index_of(order_id)?;
+        state.try_fill(index, quote);
+        Some(state.orders[index].order.clone())
+    }
+
+    pub(crate) fn open_orders(&self) -> Vec<(String, String)> {
+        let state = self.state.lock().unwrap();
+        state
+            .orders
+            .iter()
+            .filter(|o| is_open(o.order.state))
+            .map(|o| (o.order.id.clone(), o.symbol.clone()))
+            .collect()
+    }
+
+    pub(crate) fn orders(&self) -> Vec<Order> {
+        let state = self.state.lock().unwrap();
+        state.orders.iter().map(|o| o.order.clone()).collect()
+    }
+
+    pub(crate) fn find_by_ref_id(&self, ref_id: Uuid) -> Option<Order> {
+        let state = self.state.lock().unwrap();
+        state
+            .orders
+            .iter()
+            .find(|o| o.order.ref_id == Some(ref_id))
+            .map(|o| o.order.clone())
+    }
+
+    pub(crate) fn cancel(&self, order_id: &str) -> Result<(), RobinhoodErr> {
+        let mut state = self.state.lock().unwrap();
+        let index = match state.index_of(order_id) {
+            Some(index) => index,
+            None => return Err(RobinhoodErr::NotFound(order_id.to_owned())),
+        };
+        let order = &mut state.orders[index].order;
+        if !is_open(order.state) {
+            return Err(RobinhoodErr::BadResponseBody(format!(
+                "Failed to cancel order {}: order is {:?}",
+                order_id, order.state
+            )));
+        }
+        let timestamp = now_timestamp();
+        order.state = OrderState::Cancelled;
+        order.cancel = None;
+        order.updated_at = timestamp.clone();
+        order.last_transaction_at = timestamp;
+        Ok(())
+    }
+
+    pub(crate) fn position_quantity(&self, instrument: &str) -> f64 {
+        let state = self.state.lock().unwrap();
+        state.positions.get(instrument).map_or(0.0, |p| p.quantity)
+    }
+
+    pub(crate) fn account(&self) -> PaperAccount {
+        let state = self.state.lock().unwrap();
+        PaperAccount {
+            cash: state.cash,
+            positions: state.positions.values().cloned().collect(),
+        }
+    }
+}
+
+impl Robinhood {
+    /// Routes every order placement and cancellation to an in-memory broker
+    /// starting out with `starting_cash`
+    ///
+    /// Quotes, positions and accounts keep coming from the live API. Simulated orders
+    /// fill against the live quote: market orders at the ask (buy) or bid (sell),
+    /// limit and stop orders once the quote crosses their price. Orders that don't fill
+    /// right away rest until `get_order` or `update_paper_orders` sees a crossing quote.
+    ///
+    /// Enabling paper trading again resets the simulated cash, positions and orders.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{OrderRequest, Side};
+    ///
+    /// robinhood_client.enable_paper_trading(10_000.0);
+    /// let order = robinhood_client
+    ///     .place_order(&OrderRequest::market("SPY", Side::Buy, 1.0))
+    ///     .await?;
+    /// let account = robinhood_client.get_paper_account().unwrap();
+    /// println!("{:?} cash left {}", order.state, account.cash);
+    /// ```
+    pub fn enable_paper_trading(&mut self, starting_cash: f64) {
+        self.paper = Some(PaperBroker::new(starting_cash));
+    }
+
+    /// Back to live trading, the simulated state is discarded
+    pub fn disable_paper_trading(&mut self) {
+        self.paper = None;
+    }
+
+    pub fn is_paper_trading(&self) -> bool {
+        self.paper.is_some()
+    }
+
+    /// Simulated cash and positions, `None` when paper trading is disabled
+    pub fn get_paper_account(&self) -> Option<PaperAccount> {
+        self.paper.as_ref().map(|paper| paper.account())
+    }
+
+    /// Every simulated order placed since paper trading was enabled, oldest first
+    pub fn get_paper_orders(&self) -> Vec<Order> {
+        match &self.paper {
+            Some(paper) => paper.orders(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Re-evaluates every resting simulated order against a fresh quote
+    pub async fn update_paper_orders(&self) -> Result<(), RobinhoodErr> {
+        let paper = match &self.paper {
+            Some(paper) => paper,
+            None => return Ok(()),
+        };
+        for (order_id, symbol) in paper.open_orders() {
+            let quote = self.get_quote(&symbol).await?;
+            paper.update(&order_id, &quote);
+        }
+        Ok(())
+    }
+
+    /// Refreshes and returns a simulated order
+    pub(crate) async fn get_paper_order(
+        &self,
+        paper: &PaperBroker,
+        order_id: &str,
+    ) -> Result<Order, RobinhoodErr> {
+        let symbol = match paper.symbol_of(order_id) {
+            Some(symbol) => symbol,
+            None => return Err(RobinhoodErr::NotFound(order_id.to_owned())),
+        };
+        let quote = self.get_quote(&symbol).await?;
+        match paper.update(order_id, &quote) {
+            Some(order) => Ok(order),
+            None => Err(RobinhoodErr::NotFound(order_id.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(ask: &str, bid: &str) -> QuotesResponse {
+        QuotesResponse {
+            ask_price: ask.to_owned(),
+            bid_price: bid.to_owned(),
+            symbol: "SPY".to_owned(),
+            instrument: "https://api.robinhood.com/instruments/spy/".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn market_orders_fill_at_the_quote() {
+        let broker = PaperBroker::new(1000.0);
+        let buy = OrderRequest::market("SPY", Side::Buy, 2.0);
+        let order = broker.submit(&buy, &quote("101", "100"), "acc").unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(order.average_price.as_deref(), Some("101.00000000"));
+        // Resubmitting the same ref_id returns the existing order
+        let again = broker.submit(&buy, &quote("101", "100"), "acc").unwrap();
+        assert_eq!(again.id, order.id);
+
+        let sell = OrderRequest::market("SPY", Side::Sell, 1.0);
+        broker.submit(&sell, &quote("111", "110"), "acc").unwrap();
+        let account = broker.account();
+        assert_eq!(account.cash, 1000.0 - 202.0 + 110.0);
+        assert_eq!(account.positions[0].quantity, 1.0);
+        assert_eq!(account.positions[0].average_buy_price, 101.0);
+    }
+
+    #[test]
+    fn limit_orders_rest_until_crossed() {
+        let broker = PaperBroker::new(1000.0);
+        let buy = OrderRequest::limit("SPY", Side::Buy, 1.0, 95.0);
+        let order = broker.submit(&buy, &quote("101", "100"), "acc").unwrap();
+        assert_eq!(order.state, OrderState::Confirmed);
+        let order = broker.update(&order.id, &quote("94.5", "94")).unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(broker.position_quantity(&order.instrument), 1.0);
+
+        let sell = OrderRequest::limit("SPY", Side::Sell, 1.0, 120.0);
+        let order = broker.submit(&sell, &quote("101", "100"), "acc").unwrap();
+        broker.cancel(&order.id).unwrap();
+        let order = broker.update(&order.id, &quote("130", "125")).unwrap();
+        assert_eq!(order.state, OrderState::Cancelled);
+    }
+
+    #[test]
+    fn rejects_without_cash_or_shares() {
+        let broker = PaperBroker::new(100.0);
+        let buy = OrderRequest::market("SPY", Side::Buy, 2.0);
+        let order = broker.submit(&buy, &quote("101", "100"), "acc").unwrap();
+        assert_eq!(order.state, OrderState::Rejected);
+        let sell = OrderRequest::market("SPY", Side::Sell, 1.0);
+        let order = broker.submit(&sell, &quote("101", "100"), "acc").unwrap();
+        assert_eq!(order.state, OrderState::Rejected);
+        assert_eq!(broker.account().cash, 100.0);
+    }
+}
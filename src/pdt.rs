@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use crate::datetime::{eastern_day, eastern_today, format_date, parse_timestamp, sub_weekdays};
+use crate::error::RobinhoodErr;
+use crate::orders::{Order, OrderRequest, Side};
+use crate::queries::QuotesResponse;
+use crate::risk::RiskRule;
+use crate::{Robinhood, ORDERS_PATH, ROBINHOOD_API_URL};
+
+/// Day trades allowed within the rolling window before an account is flagged
+pub const DAY_TRADE_LIMIT: usize = 3;
+// Today plus the 4 business days before it
+const DAY_TRADE_WINDOW: u32 = 4;
+
+/// Day trades made within the rolling 5 business day window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DayTradeSummary {
+    /// Number of day trades in the window
+    pub day_trades: usize,
+    /// Shares bought today that have not been sold yet, by instrument URL.
+    /// Selling any of them today creates another day trade
+    pub opened_today: HashMap<String, f64>,
+    /// Shares sold today that have not been bought back yet, by instrument URL.
+    /// Buying any of them back today creates another day trade
+    pub sold_today: HashMap<String, f64>,
+}
+
+impl DayTradeSummary {
+    pub fn day_trades_remaining(&self) -> usize {
+        DAY_TRADE_LIMIT.saturating_sub(self.day_trades)
+    }
+
+    /// `true` when `order` would close a position opened today
+    pub fn is_day_trade(&self, order: &OrderRequest, instrument: &str) -> bool {
+        let open = match order.side {
+            Side::Buy => &self.sold_today,
+            Side::Sell => &self.opened_today,
+        };
+        open.get(instrument).is_some_and(|q| *q > 0.0)
+    }
+}
+
+/// Rebuilds the day trades of `orders` made on or after `window_start` (a New York trading day)
+///
+/// A day trade is an order closing, the same trading day, shares opened by an order on the
+/// other side: a sell after a buy, or a buy to cover after a sell. The partial fills of an
+/// order count once per day. Several orders closing the same morning buy each count as a
+/// day trade. Shares held overnight can't be told apart from a short sale, so selling them
+/// and buying them back the same day is counted too.
+pub(crate) fn count_day_trades(orders: &[Order], window_start: i64, today: i64) -> DayTradeSummary {
+    // Executions of each order summed by day, at the time of the first one
+    let mut fills: HashMap<(&str, i64), (i64, &String, Side, f64)> = HashMap::new();
+    for order in orders {
+        for execution in &order.executions {
+            let secs = match parse_timestamp(&execution.timestamp) {
+                Some(secs) => secs,
+                None => continue,
+            };
+            let day = eastern_day(secs);
+            if day < window_start {
+                continue;
+            }
+            let quantity = execution.quantity.parse::<f64>().unwrap_or_default();
+            let fill =
+                fills
+                    .entry((&order.id, day))
+                    .or_insert((secs, &order.instrument, order.side, 0.0));
+            fill.0 = fill.0.min(secs);
+            fill.3 += quantity;
+        }
+    }
+    let mut fills: Vec<_> = fills
+        .into_iter()
+        .map(|((_, day), (secs, instrument, side, quantity))| {
+            (secs, day, instrument, side, quantity)
+        })
+        .collect();
+    fills.sort_by_key(|(secs, ..)| *secs);
+
+    let mut summary = DayTradeSummary::default();
+    // Shares bought and not yet sold, and sold and not yet bought back, per (day, instrument)
+    let mut opened: HashMap<(i64, &String), (f64, f64)> = HashMap::new();
+    for (_, day, instrument, side, quantity) in fills {
+        let (bought, sold) = opened.entry((day, instrument)).or_insert((0.0, 0.0));
+        let (closing, opening) = match side {
+            Side::Buy => (sold, bought),
+            Side::Sell => (bought, sold),
+        };
+        let closed = closing.min(quantity);
+        if closed > 0.0 {
+            summary.day_trades += 1;
+            *closing -= closed;
+        }
+        *opening += quantity - closed;
+    }
+    for ((day, instrument), (bought, sold)) in opened {
+        if day != today {
+            continue;
+        }
+        if bought > 0.0 {
+            summary.opened_today.insert(instrument.clone(), bought);
+        }
+        if sold > 0.0 {
+            summary.sold_today.insert(instrument.clone(), sold);
+        }
+    }
+    summary
+}
+
+impl Robinhood {
+    // Orders updated on or after a New York trading day, following every page
+    async fn get_orders_updated_since(&self, day: i64) -> Result<Vec<Order>, RobinhoodErr> {
+        let url = &format!(
+            "{}{}?updated_at%5Bgte%5D={}",
+            ROBINHOOD_API_URL,
+            ORDERS_PATH,
+            format_date(day)
+        );
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Reconstructs the day trades of the rolling 5 business day window from the order history
+    ///
+    /// Market holidays are counted as business days, which can only make the window
+    /// larger than Robinhood's and the count more conservative.
+    pub async fn get_day_trades(&self) -> Result<DayTradeSummary, RobinhoodErr> {
+        let today = eastern_today();
+        let window_start = sub_weekdays(today, DAY_TRADE_WINDOW);
+        let orders = match &self.paper {
+            Some(paper) => paper.orders(),
+            // Executions happen after an order is created, so filtering on updated_at is safe
+            None => self.get_orders_updated_since(window_start).await?,
+        };
+        Ok(count_day_trades(&orders, window_start, today))
+    }
+
+    /// How many more day trades can be made before the account is flagged as a pattern day trader
+    pub async fn day_trades_remaining(&self) -> Result<usize, RobinhoodErr> {
+        Ok(self.get_day_trades().await?.day_trades_remaining())
+    }
+
+    /// Enforces `block_pattern_day_trades` and `check_buying_power` of the risk policy
+    pub(crate) async fn check_pdt(
+        &self,
+        order: &OrderRequest,
+        quote: &QuotesResponse,
+        notional: f64,
+        block_pattern_day_trades: bool,
+        check_buying_power: bool,
+    ) -> Result<(), RobinhoodErr> {
+        if block_pattern_day_trades {
+            let summary = self.get_day_trades().await?;
+            if summary.is_day_trade(order, &quote.instrument) && summary.day_trades_remaining() == 0
+            {
+                return Err(RobinhoodErr::RiskRejected {
+                    rule: RiskRule::PatternDayTrade,
+                    detail: format!(
+                        "{} {} today would be day trade number {} within 5 business days",
+                        match order.side {
+                            Side::Buy => "Buying back",
+                            Side::Sell => "Selling",
+                        },
+                        order.symbol,
+                        summary.day_trades + 1
+                    ),
+                });
+            }
+        }
+        if check_buying_power && order.side == Side::Buy {
+            let buying_power = match &self.paper {
+                Some(paper) => paper.account().cash,
+                None => self.get_account().await?.buying_power.parse::<f64>()?,
+            };
+            if notional > buying_power {
+                return Err(RobinhoodErr::RiskRejected {
+                    rule: RiskRule::BuyingPower,
+                    detail: format!(
+                        "Order notional {:.2} exceeds the buying power of {:.2}",
+                        notional, buying_power
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datetime::days_from_civil;
+    use crate::orders::{Execution, OrderState, OrderType, TimeInForce, Trigger};
+
+    fn filled(instrument: &str, side: Side, quantity: &str, timestamp: &str) -> Order {
+        fills(instrument, side, &[(quantity, timestamp)])
+    }
+
+    // One order filled by an execution per `(quantity, timestamp)`
+    fn fills(instrument: &str, side: Side, executions: &[(&str, &str)]) -> Order {
+        let (quantity, timestamp) = executions[0];
+        Order {
+            id: timestamp.to_owned(),
+            ref_id: None,
+            url: String::new(),
+            account: String::new(),
+            instrument: instrument.to_owned(),
+            cancel: None,
+            state: OrderState::Filled,
+            side,
+            order_type: OrderType::Market,
+            trigger: Trigger::Immediate,
+            time_in_force: TimeInForce::Gfd,
+            price: None,
+            stop_price: None,
+            quantity: quantity.to_owned(),
+            cumulative_quantity: quantity.to_owned(),
+            average_price: None,
+            fees: "0.00".to_owned(),
+            reject_reason: None,
+            extended_hours: false,
+            executions: executions
+                .iter()
+                .map(|(quantity, timestamp)| Execution {
+                    id: timestamp.to_string(),
+                    price: "1.00".to_owned(),
+                    quantity: quantity.to_string(),
+                    settlement_date: String::new(),
+                    timestamp: timestamp.to_string(),
+                })
+                .collect(),
+            created_at: timestamp.to_owned(),
+            updated_at: timestamp.to_owned(),
+            last_transaction_at: timestamp.to_owned(),
+        }
+    }
+
+    #[test]
+    fn reconstructs_day_trades() {
+        let orders = vec![
+            // Outside of the window
+            filled("spy", Side::Buy, "1", "2021-02-26T15:00:00Z"),
+            filled("spy", Side::Sell, "1", "2021-02-26T16:00:00Z"),
+            // Held overnight, not a day trade
+            filled("spy", Side::Buy, "2", "2021-03-01T15:00:00Z"),
+            filled("spy", Side::Sell, "2", "2021-03-02T15:00:00Z"),
+            // Two sells against one buy are two day trades
+            filled("qqq", Side::Buy, "2", "2021-03-03T15:00:00Z"),
+            filled("qqq", Side::Sell, "1", "2021-03-03T16:00:00Z"),
+            filled("qqq", Side::Sell, "1", "2021-03-03T17:00:00Z"),
+            // Bought today, still open
+            filled("spy", Side::Buy, "3", "2021-03-05T15:00:00Z"),
+        ];
+        let today = days_from_civil(2021, 3, 5);
+        let summary = count_day_trades(&orders, sub_weekdays(today, DAY_TRADE_WINDOW), today);
+        assert_eq!(summary.day_trades, 2);
+        assert_eq!(summary.day_trades_remaining(), 1);
+        assert_eq!(summary.opened_today.get("spy"), Some(&3.0));
+        assert!(summary.is_day_trade(&OrderRequest::market("SPY", Side::Sell, 1.0), "spy"));
+        assert!(!summary.is_day_trade(&OrderRequest::market("QQQ", Side::Sell, 1.0), "qqq"));
+    }
+
+    #[test]
+    fn partial_fills_and_buys_to_cover_count_once_per_order() {
+        let orders = vec![
+            filled("spy", Side::Buy, "3", "2021-03-03T15:00:00Z"),
+            // One sell filled in three executions is a single day trade
+            fills(
+                "spy",
+                Side::Sell,
+                &[
+                    ("1", "2021-03-03T16:00:00Z"),
+                    ("1", "2021-03-03T16:00:01Z"),
+                    ("1", "2021-03-03T16:00:02Z"),
+                ],
+            ),
+            // Sold, then bought back in two executions
+            filled("qqq", Side::Sell, "2", "2021-03-04T15:00:00Z"),
+            fills(
+                "qqq",
+                Side::Buy,
+                &[("1", "2021-03-04T16:00:00Z"), ("1", "2021-03-04T16:30:00Z")],
+            ),
+            // Sold today, not bought back yet
+            filled("iwm", Side::Sell, "5", "2021-03-05T15:00:00Z"),
+        ];
+        let today = days_from_civil(2021, 3, 5);
+        let summary = count_day_trades(&orders, sub_weekdays(today, DAY_TRADE_WINDOW), today);
+        assert_eq!(summary.day_trades, 2);
+        assert!(summary.opened_today.is_empty());
+        assert_eq!(summary.sold_today.get("iwm"), Some(&5.0));
+        assert!(summary.is_day_trade(&OrderRequest::market("IWM", Side::Buy, 1.0), "iwm"));
+        assert!(!summary.is_day_trade(&OrderRequest::market("IWM", Side::Sell, 1.0), "iwm"));
+    }
+}
@@ -1 +1,9 @@
+mod account;
+mod instrument;
+mod position;
 mod ticker;
+
+pub use account::Account;
+pub use instrument::Instrument;
+pub use position::Position;
+pub use ticker::QuotesResponse;
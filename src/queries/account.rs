@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, ACCOUNTS_PATH, ROBINHOOD_API_URL};
+
+impl Robinhood {
+    /// Calls api.robinhood.com/accounts/ and returns the first brokerage account
+    pub async fn get_account(&self) -> Result<Account, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ACCOUNTS_PATH);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        let accounts = decode::<AccountsResponse>(&response)?;
+        match accounts.results.into_iter().next() {
+            Some(account) => Ok(account),
+            None => Err(RobinhoodErr::BadResponseBody(
+                "No brokerage account found for this session".to_owned(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct AccountsResponse {
+    results: Vec<Account>,
+}
+
+// "url": "https://api.robinhood.com/accounts/5QR12345/",
+// "account_number": "5QR12345",
+// "type": "cash",
+// "cash": "1520.310000",
+// "buying_power": "1520.310000",
+// "cash_available_for_withdrawal": "1520.310000",
+// "portfolio_cash": "1520.310000",
+// "deactivated": false,
+// "created_at": "2019-06-11T14:23:42.121214Z",
+// "updated_at": "2021-03-04T01:00:00.000000Z"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Account {
+    pub url: String,
+    pub account_number: String,
+    #[serde(rename = "type")]
+    pub account_type: String,
+    pub cash: String,
+    pub buying_power: String,
+    pub cash_available_for_withdrawal: String,
+    pub portfolio_cash: String,
+    pub deactivated: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
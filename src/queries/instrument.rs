@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, INSTRUMENTS_PATH, ROBINHOOD_API_URL};
+
+// Robinhood rejects instrument requests with too many ids
+const MAX_IDS_PER_REQUEST: usize = 50;
+
+impl Robinhood {
+    /// Calls the `instrument` URL found in positions, orders and quotes and returns the
+    /// body as `Instrument`
+    pub async fn get_instrument(&self, instrument: &str) -> Result<Instrument, RobinhoodErr> {
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url: instrument,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        decode::<Instrument>(&response)
+    }
+
+    /// Calls api.robinhood.com/instruments/?ids=(ids) for the instrument `ids`
+    ///
+    /// Ids are batched into as few requests as possible. Unknown ids are left out of
+    /// the result.
+    pub async fn get_instruments(&self, ids: &[&str]) -> Result<Vec<Instrument>, RobinhoodErr> {
+        let mut instruments = Vec::with_capacity(ids.len());
+        for batch in ids.chunks(MAX_IDS_PER_REQUEST) {
+            let url = &format!(
+                "{}{}?ids={}",
+                ROBINHOOD_API_URL,
+                INSTRUMENTS_PATH,
+                batch.join(",")
+            );
+            let response = self
+                .req(RobinhoodReq {
+                    kind: ReqKind::Get,
+                    payload: None,
+                    url,
+                    timeout: None,
+                    retry: true,
+                })
+                .await?;
+            let res = decode::<InstrumentsResponse>(&response)?;
+            instruments.extend(res.results.into_iter().flatten());
+        }
+        Ok(instruments)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct InstrumentsResponse {
+    results: Vec<Option<Instrument>>,
+}
+
+// "id": "8f92e76f-1e0e-4478-8580-16a6ffcfaef5",
+// "url": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "symbol": "SPY",
+// "name": "SPDR S&P 500 ETF Trust",
+// "simple_name": null,
+// "tradeable": true,
+// "type": "etp"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Instrument {
+    pub id: String,
+    pub url: String,
+    pub symbol: String,
+    pub name: String,
+    pub simple_name: Option<String>,
+    pub tradeable: bool,
+    #[serde(rename = "type")]
+    pub instrument_type: String,
+}
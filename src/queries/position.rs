@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, POSITIONS_PATH, ROBINHOOD_API_URL};
+
+impl Robinhood {
+    /// Calls api.robinhood.com/positions/?nonzero=true and returns every open position
+    pub async fn get_positions(&self) -> Result<Vec<Position>, RobinhoodErr> {
+        let url = &format!("{}{}?nonzero=true", ROBINHOOD_API_URL, POSITIONS_PATH);
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        Ok(decode::<PositionsResponse>(&response)?.results)
+    }
+
+    /// Returns the number of shares held of `instrument` (the instrument URL), 0 when there is no position
+    pub async fn get_position_quantity(&self, instrument: &str) -> Result<f64, RobinhoodErr> {
+        let positions = self.get_positions().await?;
+        match positions.iter().find(|p| p.instrument == instrument) {
+            Some(position) => Ok(position.quantity.parse::<f64>()?),
+            None => Ok(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct PositionsResponse {
+    results: Vec<Position>,
+}
+
+// "url": "https://api.robinhood.com/positions/5QR12345/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "instrument": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "account_number": "5QR12345",
+// "quantity": "10.00000000",
+// "average_buy_price": "371.2500",
+// "intraday_quantity": "0.00000000",
+// "intraday_average_buy_price": "0.0000",
+// "shares_held_for_sells": "0.00000000",
+// "created_at": "2021-01-12T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Position {
+    pub url: String,
+    pub instrument: String,
+    pub account: String,
+    pub account_number: String,
+    pub quantity: String,
+    pub average_buy_price: String,
+    pub intraday_quantity: String,
+    pub intraday_average_buy_price: String,
+    pub shares_held_for_sells: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
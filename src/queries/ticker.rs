@@ -1,8 +1,14 @@
 use crate::error::RobinhoodErr;
 use serde::{Deserialize, Serialize};
 
-use crate::req::{ReqKind, RobinhoodReq};
-use crate::{Robinhood, QUOTES_PATH, ROBINHOOD_API_URL};
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{
+    HistoricalDataPoint, Interval, Robinhood, Span, QUOTES_HISTORICALS_PATH, QUOTES_PATH,
+    ROBINHOOD_API_URL,
+};
+
+// Keeps the URL of batched quote requests short enough
+const MAX_SYMBOLS_PER_REQUEST: usize = 50;
 
 impl Robinhood {
     /// Calls api.robinhood.com/quotes/(symbol)/ and returns the body as `QuotesResponse`
@@ -13,12 +19,11 @@ impl Robinhood {
                 kind: ReqKind::Get,
                 payload: None,
                 url,
+                timeout: None,
+                retry: true,
             })
             .await?;
-        match response.json::<QuotesResponse>().await {
-            Ok(res) => return Ok(res),
-            Err(e) => return Err(RobinhoodErr::RequestError(e)),
-        };
+        decode::<QuotesResponse>(&response)
     }
 
     /// Calls api.robinhood.com/quotes/(symbol)/ to retrieve a `QuotesResponse`
@@ -27,9 +32,87 @@ impl Robinhood {
         let quote = self.get_quote(symbol).await?;
         match quote.last_trade_price.parse::<f64>() {
             Ok(v) => Ok(v),
-            Err(e) => return Err(RobinhoodErr::ParseFloatError(e)),
+            Err(e) => Err(RobinhoodErr::ParseFloatError(e)),
         }
     }
+
+    /// Calls api.robinhood.com/quotes/?symbols=(symbols) for many symbols at once
+    ///
+    /// Symbols are batched into as few requests as possible. Symbols Robinhood doesn't
+    /// know are left out of the result.
+    pub async fn get_quotes(&self, symbols: &[&str]) -> Result<Vec<QuotesResponse>, RobinhoodErr> {
+        let mut quotes = Vec::with_capacity(symbols.len());
+        for batch in symbols.chunks(MAX_SYMBOLS_PER_REQUEST) {
+            let url = &format!(
+                "{}{}?symbols={}",
+                ROBINHOOD_API_URL,
+                QUOTES_PATH,
+                batch.join(",").to_uppercase()
+            );
+            let response = self
+                .req(RobinhoodReq {
+                    kind: ReqKind::Get,
+                    payload: None,
+                    url,
+                    timeout: None,
+                    retry: true,
+                })
+                .await?;
+            let res = decode::<QuotesListResponse>(&response)?;
+            quotes.extend(res.results.into_iter().flatten());
+        }
+        Ok(quotes)
+    }
+
+    /// Calls api.robinhood.com/quotes/historicals/(symbol)/ for regular hours data points
+    ///
+    /// Robinhood only serves some combinations: 5 or 10 minutes over a day or a week,
+    /// hours up to 3 months, days up to 5 years and weeks.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{Interval, Span};
+    ///
+    /// let candles = robinhood_client
+    ///     .get_historicals("SPY", Interval::Day, Span::Year)
+    ///     .await?;
+    /// ```
+    pub async fn get_historicals(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        span: Span,
+    ) -> Result<Vec<HistoricalDataPoint>, RobinhoodErr> {
+        let url = &format!(
+            "{}{}{}/?interval={}&span={}",
+            ROBINHOOD_API_URL,
+            QUOTES_HISTORICALS_PATH,
+            symbol.to_uppercase(),
+            interval.as_str(),
+            span.as_str()
+        );
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Get,
+                payload: None,
+                url,
+                timeout: None,
+                retry: true,
+            })
+            .await?;
+        Ok(decode::<HistoricalsResponse>(&response)?.historicals)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct QuotesListResponse {
+    results: Vec<Option<QuotesResponse>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct HistoricalsResponse {
+    historicals: Vec<HistoricalDataPoint>,
 }
 
 // "ask_price": "394.750000",
@@ -48,7 +131,7 @@ impl Robinhood {
 // "updated_at": "2021-03-04T01:00:00Z",
 // "instrument": "https://api.robinhood.com/instruments/8f92e76f-1e0e-4478-8580-16a6ffcfaef5/",
 // "instrument_id": "8f92e76f-1e0e-4478-8580-16a6ffcfaef5"
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct QuotesResponse {
     pub ask_price: String,
     pub ask_size: usize,
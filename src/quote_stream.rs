@@ -0,0 +1,394 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future;
+use futures::stream::{self, Stream};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::datetime::is_trading_hours_now;
+use crate::error::RobinhoodErr;
+use crate::queries::QuotesResponse;
+use crate::Robinhood;
+
+// Slowest cadence outside of trading hours, quotes barely move then
+const CLOSED_INTERVAL: Duration = Duration::from_secs(60);
+// A poll still running after this long is taken over by another stream, the one that
+// started it may not be polled anymore
+const STALLED_POLL: Duration = Duration::from_secs(30);
+
+// Fields of a quote that make it worth emitting again when they change
+type QuoteKey = (String, String, Option<String>, String, String);
+
+fn key(quote: &QuotesResponse) -> QuoteKey {
+    (
+        quote.updated_at.clone(),
+        quote.last_trade_price.clone(),
+        quote.last_extended_hours_trade_price.clone(),
+        quote.bid_price.clone(),
+        quote.ask_price.clone(),
+    )
+}
+
+struct Subscriber {
+    symbols: HashSet<String>,
+    interval: Duration,
+    // Quotes waiting to be emitted
+    queue: VecDeque<QuotesResponse>,
+    // Last emitted version of each symbol
+    seen: HashMap<String, QuoteKey>,
+}
+
+#[derive(Default)]
+struct HubState {
+    next_id: u64,
+    subscribers: HashMap<u64, Subscriber>,
+    polls: u64,
+    // Number and start of the poll a stream is making for every subscriber
+    polling: Option<(u64, Instant)>,
+    // `None` polls as soon as possible
+    next_poll: Option<Instant>,
+}
+
+impl HubState {
+    fn symbols(&self) -> Vec<String> {
+        let symbols: HashSet<&String> = self
+            .subscribers
+            .values()
+            .flat_map(|subscriber| &subscriber.symbols)
+            .collect();
+        symbols.into_iter().cloned().collect()
+    }
+
+    fn start_poll(&mut self, now: Instant) -> Step {
+        self.polls += 1;
+        self.polling = Some((self.polls, now));
+        Step::Poll(self.polls, self.symbols())
+    }
+
+    // Shortest interval of the subscribers, slowed down outside of trading hours
+    fn cadence(&self, trading_hours: bool) -> Duration {
+        let interval = self
+            .subscribers
+            .values()
+            .map(|subscriber| subscriber.interval)
+            .min()
+            .unwrap_or(CLOSED_INTERVAL);
+        if trading_hours {
+            interval
+        } else {
+            interval.max(CLOSED_INTERVAL)
+        }
+    }
+
+    // Queues every quote a subscriber follows and hasn't seen in this version yet
+    fn dispatch(&mut self, quotes: &[QuotesResponse]) {
+        for subscriber in self.subscribers.values_mut() {
+            for quote in quotes {
+                if !subscriber.symbols.contains(&quote.symbol) {
+                    continue;
+                }
+                let key = key(quote);
+                if subscriber.seen.get(&quote.symbol) != Some(&key) {
+                    subscriber.seen.insert(quote.symbol.clone(), key);
+                    subscriber.queue.push_back(quote.clone());
+                }
+            }
+        }
+    }
+}
+
+/// The poller shared by every `quote_stream` of a session
+#[derive(Default)]
+pub(crate) struct QuoteHub {
+    state: Mutex<HubState>,
+    // Woken up after every poll
+    polled: Notify,
+}
+
+impl QuoteHub {
+    fn subscribe(&self, symbols: &[&str], interval: Duration) -> Subscription<'_> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.insert(
+            id,
+            Subscriber {
+                symbols: symbols.iter().map(|s| s.to_uppercase()).collect(),
+                interval,
+                queue: VecDeque::new(),
+                seen: HashMap::new(),
+            },
+        );
+        // The new subscriber wants its first quotes right away
+        state.next_poll = None;
+        Subscription { hub: self, id }
+    }
+}
+
+// Removes the subscriber when its stream is dropped
+struct Subscription<'a> {
+    hub: &'a QuoteHub,
+    id: u64,
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.hub.state.lock().unwrap().subscribers.remove(&self.id);
+    }
+}
+
+// Hands polling over to another stream even if the polling one is dropped mid request
+struct PollGuard<'a>(&'a QuoteHub, u64);
+
+impl Drop for PollGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        // Unless it was taken over already
+        if state.polling.is_some_and(|(poll, _)| poll == self.1) {
+            state.polling = None;
+        }
+        drop(state);
+        self.0.polled.notify_waiters();
+    }
+}
+
+enum Step {
+    Emit(Box<QuotesResponse>),
+    Poll(u64, Vec<String>),
+    Wait(Option<Instant>),
+}
+
+/// Quote updates of a set of symbols, see `Robinhood::quote_stream`
+pub struct QuoteStream<'a> {
+    inner: Pin<Box<dyn Stream<Item = Result<QuotesResponse, RobinhoodErr>> + Send + 'a>>,
+}
+
+impl Stream for QuoteStream<'_> {
+    type Item = Result<QuotesResponse, RobinhoodErr>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl Robinhood {
+    /// Streams the quotes of `symbols` every time their price or `updated_at` changes
+    ///
+    /// Every stream of a session shares a single poller, fetching the quotes of all their
+    /// symbols in batched requests at the shortest `interval` asked for. Outside of
+    /// 4:00 to 20:00 New York time on weekdays it polls at most once a minute. The first
+    /// quote of each symbol is emitted right away. A failed poll is emitted as an error
+    /// by the stream that made it and polling goes on at the next interval. A poll left
+    /// unfinished for 30 seconds, by a stream that isn't polled anymore, is made again by
+    /// the next stream waiting for quotes.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::time::Duration;
+    /// use futures::StreamExt;
+    ///
+    /// let mut quotes = robinhood_client.quote_stream(&["SPY", "QQQ"], Duration::from_secs(1));
+    /// while let Some(quote) = quotes.next().await {
+    ///     let quote = quote?;
+    ///     println!("{} {}", quote.symbol, quote.last_trade_price);
+    /// }
+    /// ```
+    pub fn quote_stream(&self, symbols: &[&str], interval: Duration) -> QuoteStream<'_> {
+        let subscription = self.quotes.subscribe(symbols, interval);
+        let inner = stream::unfold(subscription, move |subscription| async move {
+            let hub = subscription.hub;
+            loop {
+                // Registered before looking at the state so no poll is missed
+                let polled = hub.polled.notified();
+                let step = {
+                    let mut state = hub.state.lock().unwrap();
+                    let now = Instant::now();
+                    let queued = state
+                        .subscribers
+                        .get_mut(&subscription.id)
+                        .and_then(|subscriber| subscriber.queue.pop_front());
+                    match queued {
+                        Some(quote) => Step::Emit(Box::new(quote)),
+                        None => match state.polling {
+                            Some((_, started)) if now < started + STALLED_POLL => {
+                                Step::Wait(Some(started + STALLED_POLL))
+                            }
+                            Some(_) => state.start_poll(now),
+                            None if state.next_poll.unwrap_or(now) <= now => state.start_poll(now),
+                            None => Step::Wait(state.next_poll),
+                        },
+                    }
+                };
+                match step {
+                    Step::Emit(quote) => return Some((Ok(*quote), subscription)),
+                    Step::Poll(poll, symbols) => {
+                        let _guard = PollGuard(hub, poll);
+                        let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+                        let result = self.get_quotes(&symbols).await;
+                        let mut state = hub.state.lock().unwrap();
+                        let cadence = state.cadence(is_trading_hours_now());
+                        state.next_poll = Some(Instant::now() + cadence);
+                        match result {
+                            Ok(quotes) => state.dispatch(&quotes),
+                            Err(e) => {
+                                drop(state);
+                                return Some((Err(e), subscription));
+                            }
+                        }
+                    }
+                    Step::Wait(Some(at)) => {
+                        let sleep = Box::pin(tokio::time::sleep_until(at));
+                        future::select(Box::pin(polled), sleep).await;
+                    }
+                    Step::Wait(None) => polled.await,
+                }
+            }
+        });
+        QuoteStream {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{BoxFuture, FutureExt};
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::{HttpRequest, HttpResponse, HttpTransport, RateLimits};
+
+    // Answers every request with the same SPY quote and counts them
+    #[derive(Default)]
+    struct Quotes {
+        sent: AtomicUsize,
+    }
+
+    impl HttpTransport for Quotes {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            let body = serde_json::json!({ "results": [quote("SPY", "380", "t1")] });
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    // Never answers the first request, like a poll whose stream isn't polled anymore
+    #[derive(Default)]
+    struct Stalled {
+        quotes: Quotes,
+    }
+
+    impl HttpTransport for Stalled {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            if self.quotes.sent.load(Ordering::SeqCst) == 0 {
+                self.quotes.sent.fetch_add(1, Ordering::SeqCst);
+                return future::pending().boxed();
+            }
+            self.quotes.send(request)
+        }
+    }
+
+    fn quote(symbol: &str, price: &str, updated_at: &str) -> QuotesResponse {
+        QuotesResponse {
+            symbol: symbol.to_owned(),
+            last_trade_price: price.to_owned(),
+            updated_at: updated_at.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dispatches_changes_to_interested_subscribers() {
+        let hub = QuoteHub::default();
+        let spy = hub.subscribe(&["spy"], Duration::from_secs(1));
+        let both = hub.subscribe(&["SPY", "QQQ"], Duration::from_secs(5));
+        let mut state = hub.state.lock().unwrap();
+        let mut symbols = state.symbols();
+        symbols.sort();
+        assert_eq!(symbols, vec!["QQQ", "SPY"]);
+
+        state.dispatch(&[quote("SPY", "380", "t1"), quote("QQQ", "310", "t1")]);
+        // Nothing changed
+        state.dispatch(&[quote("SPY", "380", "t1"), quote("QQQ", "310", "t1")]);
+        state.dispatch(&[quote("SPY", "381", "t1"), quote("QQQ", "310", "t2")]);
+        let queued = |id: u64| state.subscribers[&id].queue.len();
+        assert_eq!(queued(spy.id), 2);
+        assert_eq!(queued(both.id), 4);
+
+        assert_eq!(state.cadence(true), Duration::from_secs(1));
+        assert_eq!(state.cadence(false), CLOSED_INTERVAL);
+        drop(state);
+        drop(spy);
+        assert_eq!(hub.state.lock().unwrap().subscribers.len(), 1);
+    }
+
+    #[test]
+    fn streams_share_a_poll() {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            uuid::Uuid::new_v4(),
+        ));
+        let transport = Arc::new(Quotes::default());
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let mut first = client.quote_stream(&["SPY"], Duration::from_secs(1));
+        let mut second = client.quote_stream(&["SPY", "QQQ"], Duration::from_secs(1));
+        let quote = block_on(first.next()).unwrap().unwrap();
+        assert_eq!(quote.last_trade_price, "380");
+        let quote = block_on(second.next()).unwrap().unwrap();
+        assert_eq!(quote.symbol, "SPY");
+        assert_eq!(transport.sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn a_stalled_poll_is_taken_over() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            uuid::Uuid::new_v4(),
+        ));
+        let transport = Arc::new(Stalled::default());
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let mut first = client.quote_stream(&["SPY"], Duration::from_secs(1));
+        let mut second = client.quote_stream(&["SPY"], Duration::from_secs(1));
+        // The first stream starts polling and is never polled again
+        assert!(runtime
+            .block_on(async { futures::poll!(first.next()) })
+            .is_pending());
+        let (quote, waited) = runtime.block_on(async {
+            let started = Instant::now();
+            let quote = second.next().await.unwrap().unwrap();
+            (quote, started.elapsed())
+        });
+        assert_eq!(quote.symbol, "SPY");
+        assert!(waited >= STALLED_POLL);
+        assert_eq!(transport.quotes.sent.load(Ordering::SeqCst), 2);
+    }
+}
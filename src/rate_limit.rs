@@ -0,0 +1,297 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{Robinhood, LOG_IN_PATH, ORDERS_PATH, QUOTES_PATH};
+
+/// A token bucket budget: up to `burst` requests at once, refilled at `per_second`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    /// `requests` per second with bursts of the same size
+    pub fn per_second(requests: u32) -> Self {
+        RateLimit {
+            burst: requests,
+            per_second: requests as f64,
+        }
+    }
+
+    /// `requests` per minute with bursts of the same size
+    pub fn per_minute(requests: u32) -> Self {
+        RateLimit {
+            burst: requests,
+            per_second: requests as f64 / 60.0,
+        }
+    }
+
+    /// Changes the burst size while keeping the refill rate
+    pub fn with_burst(self, burst: u32) -> Self {
+        RateLimit { burst, ..self }
+    }
+}
+
+/// Groups of endpoints sharing a budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointGroup {
+    /// Equity, option and crypto market data
+    Quotes,
+    /// Placing, listing and cancelling orders
+    Orders,
+    /// Logging in and refreshing the token
+    Auth,
+    Other,
+}
+
+impl EndpointGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointGroup::Quotes => "quotes",
+            EndpointGroup::Orders => "orders",
+            EndpointGroup::Auth => "auth",
+            EndpointGroup::Other => "other",
+        }
+    }
+
+    pub(crate) fn of(url: &str) -> Self {
+        let path = url.split('?').next().unwrap_or(url);
+        if path.contains(LOG_IN_PATH) {
+            EndpointGroup::Auth
+        } else if path.contains(QUOTES_PATH) || path.contains("marketdata/") {
+            EndpointGroup::Quotes
+        } else if path.contains(ORDERS_PATH) {
+            EndpointGroup::Orders
+        } else {
+            EndpointGroup::Other
+        }
+    }
+}
+
+/// Budgets applied to every request of a session, `None` leaves a group unlimited
+///
+/// A request has to fit both the global budget and the budget of its group.
+/// The defaults stay well below what Robinhood tolerates before throttling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimits {
+    pub global: Option<RateLimit>,
+    pub quotes: Option<RateLimit>,
+    pub orders: Option<RateLimit>,
+    pub auth: Option<RateLimit>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            global: Some(RateLimit::per_second(5).with_burst(10)),
+            quotes: None,
+            orders: Some(RateLimit::per_second(1).with_burst(5)),
+            auth: Some(RateLimit::per_minute(3)),
+        }
+    }
+}
+
+impl RateLimits {
+    /// No limit at all, requests are sent as soon as they are made
+    pub fn unlimited() -> Self {
+        RateLimits {
+            global: None,
+            quotes: None,
+            orders: None,
+            auth: None,
+        }
+    }
+
+    fn group(&self, group: EndpointGroup) -> Option<RateLimit> {
+        match group {
+            EndpointGroup::Quotes => self.quotes,
+            EndpointGroup::Orders => self.orders,
+            EndpointGroup::Auth => self.auth,
+            EndpointGroup::Other => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Bucket {
+            limit,
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.updated = now;
+    }
+
+    // Time left until a token is available
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        if self.limit.per_second <= 0.0 {
+            return Duration::MAX;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.limit.per_second)
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    global: Option<Bucket>,
+    quotes: Option<Bucket>,
+    orders: Option<Bucket>,
+    auth: Option<Bucket>,
+}
+
+impl Buckets {
+    fn new(limits: &RateLimits, now: Instant) -> Self {
+        let bucket = |limit: Option<RateLimit>| limit.map(|limit| Bucket::new(limit, now));
+        Buckets {
+            global: bucket(limits.global),
+            quotes: bucket(limits.quotes),
+            orders: bucket(limits.orders),
+            auth: bucket(limits.auth),
+        }
+    }
+
+    fn group(&mut self, group: EndpointGroup) -> Option<&mut Bucket> {
+        match group {
+            EndpointGroup::Quotes => self.quotes.as_mut(),
+            EndpointGroup::Orders => self.orders.as_mut(),
+            EndpointGroup::Auth => self.auth.as_mut(),
+            EndpointGroup::Other => None,
+        }
+    }
+
+    // Takes a token from the global and the group bucket, or returns how long to wait.
+    // Nothing is taken unless both have one so a waiting request doesn't starve others.
+    fn try_take(&mut self, group: EndpointGroup, now: Instant) -> Result<(), Duration> {
+        let mut wait = Duration::ZERO;
+        if let Some(bucket) = self.global.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait());
+        }
+        if let Some(bucket) = self.group(group) {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait());
+        }
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+        if let Some(bucket) = self.global.as_mut() {
+            bucket.tokens -= 1.0;
+        }
+        if let Some(bucket) = self.group(group) {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+}
+
+/// Paces the requests of a session according to its `RateLimits`
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<Buckets>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(RateLimits::default())
+    }
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: RateLimits) -> Self {
+        let buckets = Mutex::new(Buckets::new(&limits, Instant::now()));
+        RateLimiter { limits, buckets }
+    }
+
+    /// Waits until a request to `group` fits the budgets, without blocking the executor.
+    /// Returns the time spent waiting
+    pub(crate) async fn acquire(&self, group: EndpointGroup) -> Duration {
+        if self.limits.global.is_none() && self.limits.group(group).is_none() {
+            return Duration::ZERO;
+        }
+        let started = Instant::now();
+        loop {
+            let taken = self.buckets.lock().unwrap().try_take(group, Instant::now());
+            match taken {
+                Ok(()) => return started.elapsed(),
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+}
+
+impl Robinhood {
+    /// Replaces the request budgets of the session, see `RateLimits`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::{RateLimit, RateLimits};
+    ///
+    /// robinhood_client.set_rate_limits(RateLimits {
+    ///     quotes: Some(RateLimit::per_second(2)),
+    ///     ..RateLimits::default()
+    /// });
+    /// ```
+    pub fn set_rate_limits(&mut self, limits: RateLimits) {
+        self.limiter = RateLimiter::new(limits);
+    }
+
+    pub fn get_rate_limits(&self) -> &RateLimits {
+        &self.limiter.limits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_pace_global_and_group_budgets() {
+        let start = Instant::now();
+        let limits = RateLimits {
+            global: Some(RateLimit::per_second(10).with_burst(3)),
+            quotes: None,
+            orders: Some(RateLimit::per_second(1).with_burst(1)),
+            auth: None,
+        };
+        let mut buckets = Buckets::new(&limits, start);
+        assert!(buckets.try_take(EndpointGroup::Orders, start).is_ok());
+        // The order budget is empty, the global one is not
+        let wait = buckets.try_take(EndpointGroup::Orders, start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(1));
+        assert!(buckets.try_take(EndpointGroup::Quotes, start).is_ok());
+        assert!(buckets.try_take(EndpointGroup::Other, start).is_ok());
+        // Global burst of 3 used up
+        let wait = buckets.try_take(EndpointGroup::Quotes, start).unwrap_err();
+        assert!(wait <= Duration::from_millis(100));
+        let later = start + Duration::from_secs(1);
+        assert!(buckets.try_take(EndpointGroup::Orders, later).is_ok());
+    }
+
+    #[test]
+    fn endpoint_groups() {
+        let group = |path: &str| EndpointGroup::of(&format!("https://api.robinhood.com/{}", path));
+        assert_eq!(group("quotes/?symbols=SPY"), EndpointGroup::Quotes);
+        assert_eq!(group("marketdata/options/?ids=1"), EndpointGroup::Quotes);
+        assert_eq!(group("options/orders/"), EndpointGroup::Orders);
+        assert_eq!(group("oauth2/token/"), EndpointGroup::Auth);
+        assert_eq!(group("accounts/"), EndpointGroup::Other);
+    }
+}
@@ -1,60 +1,351 @@
-use reqwest::{RequestBuilder, Response};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{error::RobinhoodErr, login::AgentToken, Robinhood};
+use crate::error::{snippet, status_error, strip_query, ApiError, RobinhoodErr};
+use crate::http::{execute, HttpRequest, HttpResponse};
+use crate::metrics::{
+    endpoint_label, error_kind, RATE_LIMITER_WAIT_SECONDS, REQUESTS_TOTAL,
+    REQUEST_DURATION_SECONDS, REQUEST_FAILURES_TOTAL, RETRIES_TOTAL,
+};
+use crate::{login::AgentToken, rate_limit::EndpointGroup, Robinhood};
+
+const REQUEST_ID: &str = "x-request-id";
+const RETRY_AFTER: &str = "retry-after";
 
-pub fn set_req_headers<T: AgentToken>(requestor: &T, req: RequestBuilder) -> RequestBuilder {
-    let mut rb_req = req.header("User-Agent", requestor.get_user_agent());
+pub fn req_headers<T: AgentToken>(requestor: &T) -> Vec<(String, String)> {
+    let mut headers = vec![(
+        "User-Agent".to_owned(),
+        requestor.get_user_agent().to_owned(),
+    )];
     if let Some(token) = requestor.get_token() {
-        rb_req = rb_req.header("Authorization", format!("Bearer {}", token));
+        headers.push(("Authorization".to_owned(), format!("Bearer {}", token)));
     }
-    rb_req
+    headers
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[non_exhaustive]
 pub enum ReqKind {
     Post,
     Get,
+    Delete,
+}
+
+impl ReqKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReqKind::Post => "POST",
+            ReqKind::Get => "GET",
+            ReqKind::Delete => "DELETE",
+        }
+    }
 }
 
 pub struct RobinhoodReq<'a> {
     pub kind: ReqKind,
     pub url: &'a str,
     pub payload: Option<&'a Value>,
+    pub timeout: Option<Duration>,
+    /// Retry failures following the `RetryPolicy`, only for requests that can safely be
+    /// sent twice. Submissions checking for their own `ref_id` before resending leave it off
+    pub retry: bool,
 }
 
-impl Robinhood {
-    pub async fn req(&self, request: RobinhoodReq<'_>) -> Result<Response, RobinhoodErr> {
-        match request.kind {
-            ReqKind::Post => {
-                let mut req = set_req_headers(self, reqwest::Client::new().post(request.url));
-                if let Some(payload) = request.payload {
-                    req = req.json(payload)
-                }
-                self.send_req(req).await
-            }
-            ReqKind::Get => {
-                let req = set_req_headers(self, reqwest::Client::new().get(request.url));
-                self.send_req(req).await
-            }
+/// How requests failing with a 429, a 5xx or a network error are retried
+///
+/// Only requests flagged with `RobinhoodReq::retry` are retried.
+/// The delay doubles after every attempt, starting at `base_delay` and capped at
+/// `max_delay`, with random jitter. A `Retry-After` header takes precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry, failures are returned right away
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
         }
     }
 
-    async fn send_req(&self, req: RequestBuilder) -> Result<Response, RobinhoodErr> {
-        match req.send().await {
-            Ok(res) => {
-                let status_code = res.status().as_u16();
-                // Request denied, Unauthorized
-                if status_code == 401 {
-                    return Err(RobinhoodErr::Unauthorized);
+    // Delay before retry number `retry` (0 based), between half and all of the backoff
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(0.5 + jitter / 2.0)
+    }
+}
+
+/// Reads a JSON response body, keeping the start of the body when it doesn't fit `T`
+pub(crate) fn decode<T: DeserializeOwned>(response: &HttpResponse) -> Result<T, RobinhoodErr> {
+    decode_body(&response.url, response.status, &response.body)
+}
+
+pub(crate) fn decode_body<T: DeserializeOwned>(
+    url: &str,
+    status: u16,
+    body: &str,
+) -> Result<T, RobinhoodErr> {
+    serde_json::from_str(body).map_err(|e| RobinhoodErr::Decode {
+        endpoint: strip_query(url).to_owned(),
+        status,
+        message: e.to_string(),
+        body: snippet(body),
+    })
+}
+
+/// Turns an unsuccessful response into the matching error
+pub(crate) fn check_status(res: HttpResponse) -> Result<HttpResponse, RobinhoodErr> {
+    if res.is_success() {
+        return Ok(res);
+    }
+    if res.status == 404 {
+        return Err(RobinhoodErr::NotFound(res.url));
+    }
+    // Seconds of a `Retry-After` header, HTTP dates are ignored
+    let retry_after = res
+        .header(RETRY_AFTER)
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let request_id = res.header(REQUEST_ID).map(str::to_owned);
+    let error = ApiError::from_body(res.status, &res.url, request_id, &res.body);
+    Err(status_error(error, retry_after))
+}
+
+impl Robinhood {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "robinhood.req",
+            skip_all,
+            fields(method = request.kind.as_str(), path = strip_query(request.url))
+        )
+    )]
+    pub async fn req(&self, request: RobinhoodReq<'_>) -> Result<HttpResponse, RobinhoodErr> {
+        let group = EndpointGroup::of(request.url);
+        let retries = if request.retry {
+            self.retry.max_retries
+        } else {
+            0
+        };
+        let endpoint = &endpoint_label(request.url);
+        let mut retry = 0;
+        loop {
+            let waited = self.limiter.acquire(group).await;
+            let labels = [("group", group.as_str())];
+            self.observe(RATE_LIMITER_WAIT_SECONDS, &labels, waited.as_secs_f64());
+            let result = self.send_req(&request, endpoint).await;
+            let err = match result {
+                Ok(res) => return Ok(res),
+                Err(e) => e,
+            };
+            let wait = match &err {
+                RobinhoodErr::RateLimited { retry_after, .. } if retry < retries => {
+                    retry_after.unwrap_or_else(|| self.retry.backoff(retry))
                 }
-                if status_code == 404 {
-                    return Err(RobinhoodErr::NotFound(res.url().to_string()));
+                RobinhoodErr::Server(_)
+                | RobinhoodErr::Timeout { .. }
+                | RobinhoodErr::Network { .. }
+                    if retry < retries =>
+                {
+                    self.retry.backoff(retry)
                 }
-                return Ok(res);
-            }
+                _ => {
+                    let labels = [("endpoint", endpoint.as_str()), ("kind", error_kind(&err))];
+                    self.count(REQUEST_FAILURES_TOTAL, &labels);
+                    return Err(err);
+                }
+            };
+            let labels = [
+                ("endpoint", endpoint.as_str()),
+                ("reason", error_kind(&err)),
+            ];
+            self.count(RETRIES_TOTAL, &labels);
+            warn!(
+                retry = retry + 1,
+                wait_ms = wait.as_millis() as u64,
+                error = %err,
+                "retrying request"
+            );
+            tokio::time::sleep(wait).await;
+            retry += 1;
+        }
+    }
+
+    fn build_req(&self, request: &RobinhoodReq<'_>) -> HttpRequest {
+        HttpRequest {
+            method: request.kind,
+            url: request.url.to_owned(),
+            headers: req_headers(self),
+            body: match request.kind {
+                ReqKind::Post => request.payload.cloned(),
+                ReqKind::Get | ReqKind::Delete => None,
+            },
+            timeout: request.timeout,
+        }
+    }
+
+    // Sends the request through the middlewares and turns any unsuccessful status into an error
+    async fn send_req(
+        &self,
+        request: &RobinhoodReq<'_>,
+        endpoint: &str,
+    ) -> Result<HttpResponse, RobinhoodErr> {
+        let method = request.kind.as_str();
+        let started = Instant::now();
+        let sent = execute(
+            &self.middlewares,
+            self.transport.as_ref(),
+            self.build_req(request),
+        )
+        .await;
+        let latency = started.elapsed();
+        let labels = [("endpoint", endpoint), ("method", method)];
+        self.observe(REQUEST_DURATION_SECONDS, &labels, latency.as_secs_f64());
+        let res = match sent {
+            Ok(res) => res,
             Err(e) => {
-                return Err(RobinhoodErr::RequestError(e));
+                warn!(latency_ms = latency.as_millis() as u64, error = %e, "request failed");
+                let status = error_kind(&e);
+                let labels = [
+                    ("endpoint", endpoint),
+                    ("method", method),
+                    ("status", status),
+                ];
+                self.count(REQUESTS_TOTAL, &labels);
+                return Err(e);
+            }
+        };
+        debug!(
+            status = res.status,
+            latency_ms = latency.as_millis() as u64,
+            "response received"
+        );
+        let status = res.status.to_string();
+        let labels = [
+            ("endpoint", endpoint),
+            ("method", method),
+            ("status", status.as_str()),
+        ];
+        self.count(REQUESTS_TOTAL, &labels);
+        check_status(res)
+    }
+
+    /// Replaces how failed requests are retried, see `RetryPolicy`
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry = policy;
+    }
+
+    pub fn get_retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    use crate::{HttpTransport, RateLimits};
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        for retry in 0..8 {
+            let full = (policy.base_delay * 2u32.pow(retry)).min(policy.max_delay);
+            let delay = policy.backoff(retry);
+            assert!(delay >= full / 2 && delay <= full, "{:?} {:?}", delay, full);
+        }
+    }
+
+    #[test]
+    fn decode_failures_keep_the_body() {
+        let url = "https://api.robinhood.com/quotes/SPY/?bounds=regular";
+        let err = decode_body::<Vec<String>>(url, 200, "{\"results\": null}").unwrap_err();
+        match err {
+            RobinhoodErr::Decode {
+                endpoint,
+                status,
+                body,
+                ..
+            } => {
+                assert_eq!(endpoint, "https://api.robinhood.com/quotes/SPY/");
+                assert_eq!(status, 200);
+                assert_eq!(body, "{\"results\": null}");
             }
+            e => panic!("unexpected error {}", e),
         }
     }
+
+    // Fails every request with a 503 and counts them
+    #[derive(Default)]
+    struct Unavailable {
+        sent: Mutex<usize>,
+    }
+
+    impl HttpTransport for Unavailable {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            *self.sent.lock().unwrap() += 1;
+            let response = HttpResponse {
+                status: 503,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: String::new(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn requests_without_retry_are_sent_once() {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        let transport = Arc::new(Unavailable::default());
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        // Carrying a ref_id doesn't make a request retried
+        let payload = json!({"ref_id": "6a3e1a3e-2f3c-4b2a-9f6e-0e4d1d3c2b1a"});
+        let result = block_on(client.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            url: "https://api.robinhood.com/orders/",
+            payload: Some(&payload),
+            timeout: None,
+            retry: false,
+        }));
+        assert!(matches!(result, Err(RobinhoodErr::Server(_))));
+        assert_eq!(*transport.sent.lock().unwrap(), 1);
+    }
 }
@@ -0,0 +1,609 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::datetime::today;
+use crate::error::RobinhoodErr;
+use crate::orders::{OrderRequest, OrderType, Side};
+use crate::queries::QuotesResponse;
+use crate::Robinhood;
+
+/// Limits every order has to satisfy before it is sent to Robinhood
+///
+/// Every limit is optional, a `RiskPolicy::default()` only blocks orders on halted symbols.
+/// The pattern day trade and buying power checks each cost an extra request per order.
+///
+/// # Example
+///
+/// ```ignore
+/// use robinhood::RiskPolicy;
+///
+/// let mut policy = RiskPolicy::default();
+/// policy.max_order_notional = Some(5_000.0);
+/// policy.max_daily_notional = Some(25_000.0);
+/// policy.max_position_size = Some(100.0);
+/// policy.denied_symbols.insert("GME".to_owned());
+/// // Limit prices may be at most 2% through the opposite side of the quote
+/// policy.price_collar = Some(0.02);
+/// robinhood_client.set_risk_policy(policy);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RiskPolicy {
+    /// Largest `quantity * price` allowed for a single order
+    pub max_order_notional: Option<f64>,
+    /// Largest sum of order notionals placed through this client per UTC day
+    pub max_daily_notional: Option<f64>,
+    /// Largest absolute number of shares held per symbol after the order fills
+    pub max_position_size: Option<f64>,
+    /// Per symbol overrides of `max_position_size`
+    pub position_limits: HashMap<String, f64>,
+    /// When set, only these symbols may be traded
+    pub allowed_symbols: Option<HashSet<String>>,
+    /// Symbols that may never be traded
+    pub denied_symbols: HashSet<String>,
+    /// Maximum fraction a limit price may sit above the ask (buy) or below the bid (sell)
+    pub price_collar: Option<f64>,
+    /// Reject orders while the quote reports `trading_halted`. Default is `true`
+    pub block_halted: bool,
+    /// Reject sells that would be a fourth day trade within 5 business days
+    pub block_pattern_day_trades: bool,
+    /// Reject buys with a notional above the account's `buying_power`
+    pub check_buying_power: bool,
+}
+
+impl Default for RiskPolicy {
+    fn default() -> Self {
+        RiskPolicy {
+            max_order_notional: None,
+            max_daily_notional: None,
+            max_position_size: None,
+            position_limits: HashMap::new(),
+            allowed_symbols: None,
+            denied_symbols: HashSet::new(),
+            price_collar: None,
+            block_halted: true,
+            block_pattern_day_trades: false,
+            check_buying_power: false,
+        }
+    }
+}
+
+/// The rule of a `RiskPolicy` an order violated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskRule {
+    TradingDisabled,
+    TradingHalted,
+    SymbolDenied,
+    SymbolNotAllowed,
+    MaxOrderNotional,
+    MaxDailyNotional,
+    MaxPositionSize,
+    PriceCollar,
+    PatternDayTrade,
+    BuyingPower,
+}
+
+impl fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = match self {
+            RiskRule::TradingDisabled => "trading_disabled",
+            RiskRule::TradingHalted => "trading_halted",
+            RiskRule::SymbolDenied => "symbol_denied",
+            RiskRule::SymbolNotAllowed => "symbol_not_allowed",
+            RiskRule::MaxOrderNotional => "max_order_notional",
+            RiskRule::MaxDailyNotional => "max_daily_notional",
+            RiskRule::MaxPositionSize => "max_position_size",
+            RiskRule::PriceCollar => "price_collar",
+            RiskRule::PatternDayTrade => "pattern_day_trade",
+            RiskRule::BuyingPower => "buying_power",
+        };
+        write!(f, "{}", rule)
+    }
+}
+
+fn rejected(rule: RiskRule, detail: String) -> RobinhoodErr {
+    RobinhoodErr::RiskRejected { rule, detail }
+}
+
+impl RiskPolicy {
+    fn position_limit(&self, symbol: &str) -> Option<f64> {
+        match self
+            .position_limits
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+        {
+            Some((_, limit)) => Some(*limit),
+            None => self.max_position_size,
+        }
+    }
+
+    /// Checks `symbol` against the allow and deny lists
+    pub(crate) fn check_symbol(&self, symbol: &str) -> Result<(), RobinhoodErr> {
+        if self
+            .denied_symbols
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(symbol))
+        {
+            return Err(rejected(
+                RiskRule::SymbolDenied,
+                format!("{} is on the deny list", symbol),
+            ));
+        }
+        if let Some(allowed) = &self.allowed_symbols {
+            if !allowed.iter().any(|s| s.eq_ignore_ascii_case(symbol)) {
+                return Err(rejected(
+                    RiskRule::SymbolNotAllowed,
+                    format!("{} is not on the allow list", symbol),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the order and daily notional limits
+    pub(crate) fn check_notional(
+        &self,
+        notional: f64,
+        spent_today: f64,
+    ) -> Result<(), RobinhoodErr> {
+        if let Some(max) = self.max_order_notional {
+            if notional > max {
+                return Err(rejected(
+                    RiskRule::MaxOrderNotional,
+                    format!("Order notional {:.2} exceeds {:.2}", notional, max),
+                ));
+            }
+        }
+        if let Some(max) = self.max_daily_notional {
+            if spent_today + notional > max {
+                return Err(rejected(
+                    RiskRule::MaxDailyNotional,
+                    format!(
+                        "Order notional {:.2} on top of {:.2} placed today exceeds {:.2}",
+                        notional, spent_today, max
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `order` against every rule given the latest `quote`, the shares currently held
+    /// and the notional already placed today. Returns the notional of the order.
+    pub fn check(
+        &self,
+        order: &OrderRequest,
+        quote: &QuotesResponse,
+        position: f64,
+        spent_today: f64,
+    ) -> Result<f64, RobinhoodErr> {
+        let symbol = order.symbol.to_uppercase();
+        self.check_symbol(&symbol)?;
+        if self.block_halted && quote.trading_halted {
+            return Err(rejected(
+                RiskRule::TradingHalted,
+                format!("Trading in {} is halted", symbol),
+            ));
+        }
+
+        let price = order.effective_price(quote)?;
+        if let (Some(collar), OrderType::Limit) = (self.price_collar, order.order_type) {
+            match order.side {
+                Side::Buy => {
+                    let ask = quote.ask_price.parse::<f64>()?;
+                    let max_price = ask * (1.0 + collar);
+                    if price > max_price {
+                        return Err(rejected(
+                            RiskRule::PriceCollar,
+                            format!(
+                                "Buy limit {} is above the collar {:.4} (ask {})",
+                                price, max_price, ask
+                            ),
+                        ));
+                    }
+                }
+                Side::Sell => {
+                    let bid = quote.bid_price.parse::<f64>()?;
+                    let min_price = bid * (1.0 - collar);
+                    if price < min_price {
+                        return Err(rejected(
+                            RiskRule::PriceCollar,
+                            format!(
+                                "Sell limit {} is below the collar {:.4} (bid {})",
+                                price, min_price, bid
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let notional = order.quantity * price;
+        self.check_notional(notional, spent_today)?;
+        if let Some(limit) = self.position_limit(&symbol) {
+            let resulting = match order.side {
+                Side::Buy => position + order.quantity,
+                Side::Sell => position - order.quantity,
+            };
+            if resulting.abs() > limit {
+                return Err(rejected(
+                    RiskRule::MaxPositionSize,
+                    format!(
+                        "Position in {} would be {} shares, the limit is {}",
+                        symbol, resulting, limit
+                    ),
+                ));
+            }
+        }
+        Ok(notional)
+    }
+}
+
+// Notional placed through the client on a given UTC day
+#[derive(Debug, Default)]
+struct DailyNotional {
+    day: i64,
+    total: f64,
+}
+
+/// Runtime state shared by the risk checks of a `Robinhood` client
+#[derive(Debug, Default)]
+pub(crate) struct RiskGuard {
+    policy: Option<RiskPolicy>,
+    trading_disabled: AtomicBool,
+    daily: Mutex<DailyNotional>,
+}
+
+impl RiskGuard {
+    fn spent_today(&self) -> f64 {
+        let daily = self.daily.lock().unwrap();
+        if daily.day == today() {
+            daily.total
+        } else {
+            0.0
+        }
+    }
+
+    // Counts `notional` as placed today once `check` accepted the notional already placed,
+    // under a single lock so concurrent orders can't both fit in the same budget
+    fn reserve<F>(&self, notional: f64, check: F) -> Result<Reservation<'_>, RobinhoodErr>
+    where
+        F: FnOnce(f64) -> Result<(), RobinhoodErr>,
+    {
+        let mut daily = self.daily.lock().unwrap();
+        let today = today();
+        if daily.day != today {
+            daily.day = today;
+            daily.total = 0.0;
+        }
+        check(daily.total)?;
+        daily.total += notional;
+        Ok(Reservation {
+            risk: self,
+            day: today,
+            notional,
+            committed: false,
+        })
+    }
+}
+
+/// Notional of an order being submitted, counted against `max_daily_notional` until the
+/// order is placed. Handed back when dropped without `settle`
+pub(crate) struct Reservation<'a> {
+    risk: &'a RiskGuard,
+    day: i64,
+    notional: f64,
+    committed: bool,
+}
+
+impl Reservation<'_> {
+    /// Keeps the notional spent unless the order was rejected outright. After an ambiguous
+    /// failure or an unreadable response the order may have been placed all the same
+    pub(crate) fn settle<T>(
+        mut self,
+        submitted: Result<T, RobinhoodErr>,
+    ) -> Result<T, RobinhoodErr> {
+        self.committed = match &submitted {
+            Ok(_) => true,
+            Err(e) => e.is_ambiguous() || matches!(e, RobinhoodErr::Decode { .. }),
+        };
+        submitted
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let mut daily = self.risk.daily.lock().unwrap();
+        if daily.day == self.day {
+            daily.total -= self.notional;
+        }
+    }
+}
+
+impl Robinhood {
+    /// Every order placed by this client has to pass `policy`
+    pub fn set_risk_policy(&mut self, policy: RiskPolicy) {
+        self.risk.policy = Some(policy);
+    }
+
+    pub fn clear_risk_policy(&mut self) {
+        self.risk.policy = None;
+    }
+
+    pub fn get_risk_policy(&self) -> Option<&RiskPolicy> {
+        self.risk.policy.as_ref()
+    }
+
+    /// Global kill switch, while disabled every order is rejected with `RiskRule::TradingDisabled`
+    ///
+    /// Works with or without a `RiskPolicy` and only needs a shared reference,
+    /// so it can be flipped while other tasks are placing orders.
+    pub fn set_trading_enabled(&self, enabled: bool) {
+        self.risk.trading_disabled.store(!enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_trading_enabled(&self) -> bool {
+        !self.risk.trading_disabled.load(Ordering::SeqCst)
+    }
+
+    /// Runs an option or crypto order on `symbol` through the kill switch,
+    /// the symbol lists and the notional limits, and reserves its notional
+    pub(crate) fn check_basic_risk(
+        &self,
+        symbol: &str,
+        notional: f64,
+    ) -> Result<Reservation<'_>, RobinhoodErr> {
+        if !self.is_trading_enabled() {
+            return Err(rejected(
+                RiskRule::TradingDisabled,
+                "Trading is disabled on this client".to_owned(),
+            ));
+        }
+        match &self.risk.policy {
+            Some(policy) => {
+                policy.check_symbol(&symbol.to_uppercase())?;
+                self.risk
+                    .reserve(notional, |spent| policy.check_notional(notional, spent))
+            }
+            None => self.risk.reserve(notional, |_| Ok(())),
+        }
+    }
+
+    /// Runs `order` through the kill switch and the risk policy, and reserves the notional
+    /// of the order until it is placed
+    pub(crate) async fn check_risk(
+        &self,
+        order: &OrderRequest,
+        quote: &QuotesResponse,
+    ) -> Result<Reservation<'_>, RobinhoodErr> {
+        if !self.is_trading_enabled() {
+            return Err(rejected(
+                RiskRule::TradingDisabled,
+                "Trading is disabled on this client".to_owned(),
+            ));
+        }
+        let policy = match &self.risk.policy {
+            Some(policy) => policy,
+            None => {
+                let notional = order.effective_price(quote)? * order.quantity;
+                return self.risk.reserve(notional, |_| Ok(()));
+            }
+        };
+        // Only hit positions/ when a position limit applies to the symbol
+        let position = if policy
+            .position_limit(&order.symbol.to_uppercase())
+            .is_some()
+        {
+            match &self.paper {
+                Some(paper) => paper.position_quantity(&quote.instrument),
+                None => self.get_position_quantity(&quote.instrument).await?,
+            }
+        } else {
+            0.0
+        };
+        let notional = policy.check(order, quote, position, self.risk.spent_today())?;
+        self.check_pdt(
+            order,
+            quote,
+            notional,
+            policy.block_pattern_day_trades,
+            policy.check_buying_power,
+        )
+        .await?;
+        // Checked again now that every await is behind, the other orders placed meanwhile
+        // count too
+        self.risk
+            .reserve(notional, |spent| policy.check_notional(notional, spent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::task::Poll;
+
+    use crate::{Account, HttpRequest, HttpResponse, HttpTransport, RateLimits};
+
+    fn quote() -> QuotesResponse {
+        QuotesResponse {
+            ask_price: "100.000000".to_owned(),
+            bid_price: "99.000000".to_owned(),
+            symbol: "SPY".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn rule_of(result: Result<f64, RobinhoodErr>) -> RiskRule {
+        match result {
+            Err(RobinhoodErr::RiskRejected { rule, .. }) => rule,
+            other => panic!("Expected a risk rejection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_policy_only_blocks_halted() {
+        let policy = RiskPolicy::default();
+        let order = OrderRequest::market("SPY", Side::Buy, 3.0);
+        assert_eq!(policy.check(&order, &quote(), 0.0, 0.0).unwrap(), 300.0);
+        let halted = QuotesResponse {
+            trading_halted: true,
+            ..quote()
+        };
+        assert_eq!(
+            rule_of(policy.check(&order, &halted, 0.0, 0.0)),
+            RiskRule::TradingHalted
+        );
+    }
+
+    #[test]
+    fn symbol_lists() {
+        let mut policy = RiskPolicy::default();
+        policy.denied_symbols.insert("spy".to_owned());
+        let order = OrderRequest::market("SPY", Side::Buy, 1.0);
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 0.0, 0.0)),
+            RiskRule::SymbolDenied
+        );
+
+        let policy = RiskPolicy {
+            allowed_symbols: Some(vec!["QQQ".to_owned()].into_iter().collect()),
+            ..Default::default()
+        };
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 0.0, 0.0)),
+            RiskRule::SymbolNotAllowed
+        );
+    }
+
+    #[test]
+    fn notional_limits() {
+        let policy = RiskPolicy {
+            max_order_notional: Some(500.0),
+            max_daily_notional: Some(1000.0),
+            ..Default::default()
+        };
+        let order = OrderRequest::limit("SPY", Side::Buy, 6.0, 99.5);
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 0.0, 0.0)),
+            RiskRule::MaxOrderNotional
+        );
+        let order = OrderRequest::limit("SPY", Side::Buy, 4.0, 99.5);
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 0.0, 700.0)),
+            RiskRule::MaxDailyNotional
+        );
+        assert_eq!(policy.check(&order, &quote(), 0.0, 500.0).unwrap(), 398.0);
+    }
+
+    #[test]
+    fn position_limits_and_collars() {
+        let mut policy = RiskPolicy {
+            max_position_size: Some(10.0),
+            price_collar: Some(0.01),
+            ..Default::default()
+        };
+        policy.position_limits.insert("spy".to_owned(), 5.0);
+        let order = OrderRequest::limit("SPY", Side::Buy, 2.0, 100.5);
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 4.0, 0.0)),
+            RiskRule::MaxPositionSize
+        );
+        let order = OrderRequest::limit("SPY", Side::Sell, 2.0, 97.0);
+        assert_eq!(
+            rule_of(policy.check(&order, &quote(), 4.0, 0.0)),
+            RiskRule::PriceCollar
+        );
+        let order = OrderRequest::limit("SPY", Side::Buy, 1.0, 100.5);
+        assert!(policy.check(&order, &quote(), 4.0, 0.0).is_ok());
+    }
+
+    // Quotes SPY and yields once before answering accounts/, so that concurrent orders
+    // are all checked before any of them is submitted
+    struct Market;
+
+    impl HttpTransport for Market {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            let body = if request.url.contains("accounts/") {
+                json!({ "results": [Account::default()] })
+            } else {
+                json!(QuotesResponse {
+                    last_trade_price: "100.000000".to_owned(),
+                    instrument: "https://api.robinhood.com/instruments/spy/".to_owned(),
+                    ..quote()
+                })
+            };
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            };
+            let mut yielded = false;
+            let yield_once = future::poll_fn(move |cx| {
+                if yielded || !request.url.contains("accounts/") {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            });
+            yield_once.map(|_| Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn concurrent_orders_share_the_daily_notional() {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            uuid::Uuid::new_v4(),
+        ));
+        client.set_transport(Arc::new(Market));
+        client.set_rate_limits(RateLimits::unlimited());
+        client.enable_paper_trading(10_000.0);
+        client.set_risk_policy(RiskPolicy {
+            max_daily_notional: Some(500.0),
+            ..Default::default()
+        });
+        let first = OrderRequest::market("SPY", Side::Buy, 3.0);
+        let second = OrderRequest::market("SPY", Side::Buy, 3.0);
+        let (first, second) = block_on(future::join(
+            client.place_order(&first),
+            client.place_order(&second),
+        ));
+        assert!(first.is_ok());
+        assert_eq!(rule_of(second.map(|_| 0.0)), RiskRule::MaxDailyNotional);
+        assert_eq!(client.risk.spent_today(), 300.0);
+
+        // A reservation is handed back when its order isn't placed
+        let quote = quote();
+        let order = OrderRequest::market("SPY", Side::Buy, 2.0);
+        let reservation = block_on(client.check_risk(&order, &quote)).unwrap();
+        assert_eq!(client.risk.spent_today(), 500.0);
+        drop(reservation);
+        assert_eq!(client.risk.spent_today(), 300.0);
+        let reservation = block_on(client.check_risk(&order, &quote)).unwrap();
+        let rejected = RobinhoodErr::InvalidOrder("rejected".to_owned());
+        assert!(reservation.settle::<()>(Err(rejected)).is_err());
+        assert_eq!(client.risk.spent_today(), 300.0);
+
+        // Unless the order may have been placed
+        let reservation = block_on(client.check_risk(&order, &quote)).unwrap();
+        let timeout = RobinhoodErr::Timeout {
+            endpoint: "https://api.robinhood.com/orders/".to_owned(),
+        };
+        assert!(reservation.settle::<()>(Err(timeout)).is_err());
+        assert_eq!(client.risk.spent_today(), 500.0);
+    }
+}
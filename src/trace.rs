@@ -0,0 +1,34 @@
+// Thin wrappers around the `tracing` macros so instrumentation costs nothing when the
+// `tracing` feature is disabled. Arguments are not evaluated in that case.
+//
+// Never pass tokens, passwords, MFA codes or request payloads to these macros.
+
+/// Printed in place of secrets by the `Debug` impls of the login types
+pub(crate) const REDACTED: &str = "<redacted>";
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::debug!($($arg)*);
+        }
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::info!($($arg)*);
+        }
+    };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::warn!($($arg)*);
+        }
+    };
+}
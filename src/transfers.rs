@@ -0,0 +1,446 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::error::RobinhoodErr;
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, ACH_RELATIONSHIPS_PATH, ACH_TRANSFERS_PATH, ROBINHOOD_API_URL};
+
+// Most recent transfers searched for one whose response was lost
+const TRANSFER_LOOKUP: usize = 100;
+
+impl Robinhood {
+    /// Calls api.robinhood.com/ach/relationships/ and returns every linked bank account
+    pub async fn get_ach_relationships(&self) -> Result<Vec<AchRelationship>, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ACH_RELATIONSHIPS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Calls api.robinhood.com/ach/transfers/ and returns the `limit` most recent
+    /// deposits and withdrawals, newest first, or every transfer when `limit` is `None`
+    pub async fn get_transfers(&self, limit: Option<usize>) -> Result<Vec<Transfer>, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ACH_TRANSFERS_PATH);
+        self.paginate(url).collect_all(limit).await
+    }
+
+    /// Checks a transfer before it is initiated, first half of the confirmation guard
+    ///
+    /// The amount has to be positive and the bank account one of the approved
+    /// `get_ach_relationships`. Nothing is sent to Robinhood until the returned
+    /// `TransferConfirmation` is passed to `initiate_transfer`, show its summary to
+    /// whoever has to approve the transfer in between.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use robinhood::TransferRequest;
+    ///
+    /// let bank = &robinhood_client.get_ach_relationships().await?[0];
+    /// let confirmation = robinhood_client
+    ///     .prepare_transfer(TransferRequest::deposit(bank, 500.0))
+    ///     .await?;
+    /// println!("{}", confirmation);
+    /// // Deposit $500.00 from Checking (account ending in 1234)
+    /// let transfer = robinhood_client.initiate_transfer(confirmation).await?;
+    /// println!("lands on {:?}", transfer.expected_landing_date);
+    /// ```
+    pub async fn prepare_transfer(
+        &self,
+        request: TransferRequest,
+    ) -> Result<TransferConfirmation, RobinhoodErr> {
+        let relationships = self.get_ach_relationships().await?;
+        let relationship = check_transfer(&request, relationships)?;
+        Ok(TransferConfirmation {
+            request,
+            relationship,
+        })
+    }
+
+    /// Initiates a confirmed transfer at api.robinhood.com/ach/transfers/
+    ///
+    /// The transfer is sent once and never retried. When its outcome is unknown (timeout,
+    /// network or server error) it is looked up among the recent transfers by `ref_id`
+    /// and returned if Robinhood got it, otherwise the error is. So is a successful
+    /// response with an unreadable body, `RobinhoodErr::Decode` then means the transfer
+    /// was created but couldn't be found yet. Paper trading clients refuse to move real
+    /// money.
+    pub async fn initiate_transfer(
+        &self,
+        confirmation: TransferConfirmation,
+    ) -> Result<Transfer, RobinhoodErr> {
+        if self.paper.is_some() {
+            return Err(RobinhoodErr::InvalidTransfer(
+                "transfers are disabled in paper trading".to_owned(),
+            ));
+        }
+        let request = &confirmation.request;
+        let url = &format!("{}{}", ROBINHOOD_API_URL, ACH_TRANSFERS_PATH);
+        let payload = json!({
+            "ach_relationship": request.ach_relationship,
+            "amount": format!("{:.2}", request.amount),
+            "direction": request.direction,
+            "ref_id": request.ref_id,
+        });
+        let err = match self
+            .req(RobinhoodReq {
+                kind: ReqKind::Post,
+                payload: Some(&payload),
+                url,
+                timeout: None,
+                retry: false,
+            })
+            .await
+        {
+            Ok(response) => match decode::<Transfer>(&response) {
+                Err(e @ RobinhoodErr::Decode { .. }) => e,
+                decoded => return decoded,
+            },
+            Err(e) if e.is_ambiguous() => e,
+            Err(e) => return Err(e),
+        };
+        // Robinhood may have created the transfer before the failure
+        let transfers = self.get_transfers(Some(TRANSFER_LOOKUP)).await?;
+        match transfers
+            .into_iter()
+            .find(|transfer| transfer.ref_id == Some(request.ref_id))
+        {
+            Some(transfer) => Ok(transfer),
+            None => Err(err),
+        }
+    }
+}
+
+// The approved relationship `request` moves money with
+fn check_transfer(
+    request: &TransferRequest,
+    relationships: Vec<AchRelationship>,
+) -> Result<AchRelationship, RobinhoodErr> {
+    if !request.amount.is_finite() || request.amount < 0.01 {
+        return Err(RobinhoodErr::InvalidTransfer(format!(
+            "amount must be at least $0.01, got {}",
+            request.amount
+        )));
+    }
+    let relationship = relationships
+        .into_iter()
+        .find(|relationship| relationship.url == request.ach_relationship)
+        .ok_or_else(|| {
+            RobinhoodErr::InvalidTransfer(format!(
+                "no linked bank account {}",
+                request.ach_relationship
+            ))
+        })?;
+    if !relationship.is_approved() {
+        return Err(RobinhoodErr::InvalidTransfer(format!(
+            "bank account {} is {}",
+            relationship.bank_account_nickname, relationship.state
+        )));
+    }
+    Ok(relationship)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    /// From the bank to Robinhood
+    Deposit,
+    /// From Robinhood to the bank
+    Withdraw,
+}
+
+/// A deposit or withdrawal to check with `Robinhood::prepare_transfer`
+#[derive(Debug, Clone)]
+pub struct TransferRequest {
+    /// URL of the `AchRelationship`
+    pub ach_relationship: String,
+    pub direction: TransferDirection,
+    /// Dollars, rounded to cents
+    pub amount: f64,
+    /// Sent with the transfer so Robinhood can deduplicate submissions
+    pub ref_id: Uuid,
+}
+
+impl TransferRequest {
+    pub fn deposit(relationship: &AchRelationship, amount: f64) -> Self {
+        TransferRequest::new(relationship, TransferDirection::Deposit, amount)
+    }
+
+    pub fn withdraw(relationship: &AchRelationship, amount: f64) -> Self {
+        TransferRequest::new(relationship, TransferDirection::Withdraw, amount)
+    }
+
+    fn new(relationship: &AchRelationship, direction: TransferDirection, amount: f64) -> Self {
+        TransferRequest {
+            ach_relationship: relationship.url.clone(),
+            direction,
+            amount,
+            ref_id: Uuid::new_v4(),
+        }
+    }
+}
+
+/// A checked `TransferRequest`, only `Robinhood::prepare_transfer` creates one
+#[derive(Debug)]
+pub struct TransferConfirmation {
+    request: TransferRequest,
+    relationship: AchRelationship,
+}
+
+impl TransferConfirmation {
+    pub fn request(&self) -> &TransferRequest {
+        &self.request
+    }
+
+    pub fn relationship(&self) -> &AchRelationship {
+        &self.relationship
+    }
+}
+
+impl fmt::Display for TransferConfirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (action, preposition) = match self.request.direction {
+            TransferDirection::Deposit => ("Deposit", "from"),
+            TransferDirection::Withdraw => ("Withdraw", "to"),
+        };
+        write!(
+            f,
+            "{} ${:.2} {} {} (account ending in {})",
+            action,
+            self.request.amount,
+            preposition,
+            self.relationship.bank_account_nickname,
+            self.relationship.bank_account_number
+        )
+    }
+}
+
+// "id": "1c5f1a2b-3d4e-4f5a-8b9c-0d1e2f3a4b5c",
+// "url": "https://api.robinhood.com/ach/relationships/1c5f1a2b-3d4e-4f5a-8b9c-0d1e2f3a4b5c/",
+// "account": "https://api.robinhood.com/accounts/5QR12345/",
+// "bank_account_nickname": "Checking",
+// "bank_account_number": "1234",
+// "bank_account_type": "checking",
+// "bank_routing_number": "021000021",
+// "state": "approved",
+// "verified": true,
+// "created_at": "2021-01-12T15:02:11.482381Z"
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct AchRelationship {
+    pub id: String,
+    pub url: String,
+    pub account: String,
+    pub bank_account_nickname: String,
+    /// Last 4 digits only
+    pub bank_account_number: String,
+    pub bank_account_type: String,
+    pub bank_routing_number: String,
+    pub state: String,
+    pub verified: bool,
+    pub created_at: String,
+}
+
+impl AchRelationship {
+    /// Money can be moved with this bank account
+    pub fn is_approved(&self) -> bool {
+        self.state == "approved" && self.verified
+    }
+}
+
+// "id": "6d7e8f9a-0b1c-4d2e-9f3a-4b5c6d7e8f9a",
+// "url": "https://api.robinhood.com/ach/transfers/6d7e8f9a-0b1c-4d2e-9f3a-4b5c6d7e8f9a/",
+// "ach_relationship": "https://api.robinhood.com/ach/relationships/1c5f1a2b-3d4e-4f5a-8b9c-0d1e2f3a4b5c/",
+// "amount": "500.00",
+// "direction": "deposit",
+// "state": "pending",
+// "fees": "0.00",
+// "status_description": "",
+// "expected_landing_date": "2021-03-08",
+// "cancel": "https://api.robinhood.com/ach/transfers/6d7e8f9a-0b1c-4d2e-9f3a-4b5c6d7e8f9a/cancel/",
+// "ref_id": "0e4d1d3c-2b1a-4f6e-9a3e-6a3e1a3e2f3c",
+// "created_at": "2021-03-04T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transfer {
+    pub id: String,
+    pub url: String,
+    pub ach_relationship: String,
+    pub amount: String,
+    pub direction: TransferDirection,
+    /// "pending", "completed", "cancelled", "failed" or "reversed"
+    pub state: String,
+    pub fees: String,
+    pub status_description: String,
+    pub expected_landing_date: Option<String>,
+    /// URL to cancel the transfer, `None` once it can't be cancelled anymore
+    pub cancel: Option<String>,
+    pub ref_id: Option<Uuid>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Transfer {
+    /// The money hasn't landed yet
+    pub fn is_pending(&self) -> bool {
+        self.state == "pending"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{HttpRequest, HttpResponse, HttpTransport, RateLimits};
+
+    // Times every transfer out, or answers it with garbage if `garbled`, and lists
+    // `listed` as the recent transfers, counting POSTs
+    struct Bank {
+        listed: Vec<Value>,
+        garbled: bool,
+        posted: Mutex<usize>,
+    }
+
+    impl HttpTransport for Bank {
+        fn send<'a>(
+            &'a self,
+            request: &'a HttpRequest,
+        ) -> BoxFuture<'a, Result<HttpResponse, RobinhoodErr>> {
+            if request.method == ReqKind::Post {
+                *self.posted.lock().unwrap() += 1;
+                if self.garbled {
+                    let response = HttpResponse {
+                        status: 200,
+                        url: request.url.clone(),
+                        headers: Vec::new(),
+                        body: "<html>".to_owned(),
+                    };
+                    return future::ready(Ok(response)).boxed();
+                }
+                return future::ready(Err(RobinhoodErr::Timeout {
+                    endpoint: request.url.clone(),
+                }))
+                .boxed();
+            }
+            let response = HttpResponse {
+                status: 200,
+                url: request.url.clone(),
+                headers: Vec::new(),
+                body: json!({ "results": self.listed, "next": null }).to_string(),
+            };
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn transfer(ref_id: Uuid) -> Value {
+        json!({
+            "id": "6d7e8f9a", "url": "", "ach_relationship": "", "amount": "500.00",
+            "direction": "deposit", "state": "pending", "fees": "0.00",
+            "status_description": "", "expected_landing_date": null, "cancel": null,
+            "ref_id": ref_id, "created_at": "", "updated_at": "",
+        })
+    }
+
+    // Initiates a $500 deposit over `Bank`, returning the result and the number of POSTs
+    fn initiate(
+        garbled: bool,
+        listed: impl FnOnce(Uuid) -> Vec<Value>,
+    ) -> (Result<Transfer, RobinhoodErr>, usize) {
+        let mut client = block_on(Robinhood::token_login(
+            "token".to_owned(),
+            "refresh".to_owned(),
+            Uuid::new_v4(),
+        ));
+        let relationship = checking("approved");
+        let request = TransferRequest::deposit(&relationship, 500.0);
+        let transport = Arc::new(Bank {
+            listed: listed(request.ref_id),
+            garbled,
+            posted: Mutex::new(0),
+        });
+        client.set_transport(transport.clone());
+        client.set_rate_limits(RateLimits::unlimited());
+        let confirmation = TransferConfirmation {
+            request,
+            relationship,
+        };
+        let result = block_on(client.initiate_transfer(confirmation));
+        let posted = *transport.posted.lock().unwrap();
+        (result, posted)
+    }
+
+    fn checking(state: &str) -> AchRelationship {
+        AchRelationship {
+            url: "https://api.robinhood.com/ach/relationships/1c5f/".to_owned(),
+            bank_account_nickname: "Checking".to_owned(),
+            bank_account_number: "1234".to_owned(),
+            state: state.to_owned(),
+            verified: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transfers_are_checked_before_confirmation() {
+        let bank = checking("approved");
+        let request = TransferRequest::deposit(&bank, 500.0);
+        let relationship = check_transfer(&request, vec![bank.clone()]).unwrap();
+        let confirmation = TransferConfirmation {
+            request,
+            relationship,
+        };
+        assert_eq!(
+            confirmation.to_string(),
+            "Deposit $500.00 from Checking (account ending in 1234)"
+        );
+
+        let rejected = |request: &TransferRequest, relationships| {
+            matches!(
+                check_transfer(request, relationships),
+                Err(RobinhoodErr::InvalidTransfer(_))
+            )
+        };
+        assert!(rejected(
+            &TransferRequest::withdraw(&bank, 0.0),
+            vec![bank.clone()]
+        ));
+        assert!(rejected(
+            &TransferRequest::withdraw(&bank, f64::NAN),
+            vec![bank.clone()]
+        ));
+        assert!(rejected(&TransferRequest::withdraw(&bank, 10.0), vec![]));
+        let pending = checking("pending");
+        assert!(rejected(
+            &TransferRequest::deposit(&pending, 10.0),
+            vec![pending.clone()]
+        ));
+    }
+
+    #[test]
+    fn timed_out_transfers_are_looked_up_and_never_resent() {
+        let (found, posted) = initiate(false, |ref_id| {
+            vec![transfer(Uuid::new_v4()), transfer(ref_id)]
+        });
+        assert_eq!(posted, 1);
+        assert!(found.unwrap().is_pending());
+
+        let (lost, posted) = initiate(false, |_| vec![transfer(Uuid::new_v4())]);
+        assert_eq!(posted, 1);
+        assert!(matches!(lost, Err(RobinhoodErr::Timeout { .. })));
+    }
+
+    #[test]
+    fn unreadable_transfers_are_looked_up_and_never_resent() {
+        let (found, posted) = initiate(true, |ref_id| vec![transfer(ref_id)]);
+        assert_eq!(posted, 1);
+        assert!(found.unwrap().is_pending());
+
+        let (lost, posted) = initiate(true, |_| vec![]);
+        assert_eq!(posted, 1);
+        assert!(matches!(lost, Err(RobinhoodErr::Decode { .. })));
+    }
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::RobinhoodErr;
+use crate::queries::{Instrument, QuotesResponse};
+use crate::req::{decode, ReqKind, RobinhoodReq};
+use crate::{Robinhood, ROBINHOOD_API_URL, WATCHLISTS_PATH, WATCHLIST_ITEMS_PATH};
+
+// Object type of stocks and ETFs in list items
+const INSTRUMENT: &str = "instrument";
+
+impl Robinhood {
+    /// Calls api.robinhood.com/midlands/lists/default/ and returns every watchlist
+    pub async fn get_watchlists(&self) -> Result<Vec<Watchlist>, RobinhoodErr> {
+        let url = &format!("{}{}default/", ROBINHOOD_API_URL, WATCHLISTS_PATH);
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Finds a watchlist by its display name, ignoring case
+    pub async fn get_watchlist(&self, name: &str) -> Result<Watchlist, RobinhoodErr> {
+        let watchlists = self.get_watchlists().await?;
+        match watchlists
+            .into_iter()
+            .find(|watchlist| watchlist.display_name.eq_ignore_ascii_case(name))
+        {
+            Some(watchlist) => Ok(watchlist),
+            None => Err(RobinhoodErr::NotFound(format!(
+                "{}{} {}",
+                ROBINHOOD_API_URL, WATCHLISTS_PATH, name
+            ))),
+        }
+    }
+
+    pub async fn create_watchlist(&self, name: &str) -> Result<Watchlist, RobinhoodErr> {
+        let url = &format!("{}{}", ROBINHOOD_API_URL, WATCHLISTS_PATH);
+        let payload = json!({ "display_name": name });
+        let response = self
+            .req(RobinhoodReq {
+                kind: ReqKind::Post,
+                payload: Some(&payload),
+                url,
+                timeout: None,
+                retry: false,
+            })
+            .await?;
+        decode::<Watchlist>(&response)
+    }
+
+    pub async fn delete_watchlist(&self, list_id: &str) -> Result<(), RobinhoodErr> {
+        let url = &format!("{}{}{}/", ROBINHOOD_API_URL, WATCHLISTS_PATH, list_id);
+        self.req(RobinhoodReq {
+            kind: ReqKind::Delete,
+            payload: None,
+            url,
+            timeout: None,
+            retry: true,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Calls api.robinhood.com/midlands/lists/items/?list_id=(list_id), items come in
+    /// the order shown in the app
+    pub async fn get_watchlist_items(
+        &self,
+        list_id: &str,
+    ) -> Result<Vec<WatchlistItem>, RobinhoodErr> {
+        let url = &format!(
+            "{}{}?list_id={}",
+            ROBINHOOD_API_URL, WATCHLIST_ITEMS_PATH, list_id
+        );
+        self.paginate(url).collect_all(None).await
+    }
+
+    /// Adds stocks to a watchlist, symbols already in it are left alone
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let watchlist = robinhood_client.get_watchlist("Screener").await?;
+    /// robinhood_client.add_to_watchlist(&watchlist.id, &["SPY", "QQQ"]).await?;
+    /// let quotes = robinhood_client.get_watchlist_quotes(&watchlist.id).await?;
+    /// ```
+    pub async fn add_to_watchlist(
+        &self,
+        list_id: &str,
+        symbols: &[&str],
+    ) -> Result<(), RobinhoodErr> {
+        self.update_watchlist(list_id, symbols, "create").await
+    }
+
+    pub async fn remove_from_watchlist(
+        &self,
+        list_id: &str,
+        symbols: &[&str],
+    ) -> Result<(), RobinhoodErr> {
+        self.update_watchlist(list_id, symbols, "delete").await
+    }
+
+    // Creates or deletes the items of `symbols`, resolved to instruments with one quote call
+    async fn update_watchlist(
+        &self,
+        list_id: &str,
+        symbols: &[&str],
+        operation: &str,
+    ) -> Result<(), RobinhoodErr> {
+        let quotes = self.get_quotes(symbols).await?;
+        if let Some(missing) = symbols
+            .iter()
+            .find(|symbol| !quotes.iter().any(|q| q.symbol.eq_ignore_ascii_case(symbol)))
+        {
+            return Err(RobinhoodErr::NotFound(format!(
+                "{}quotes/ {}",
+                ROBINHOOD_API_URL, missing
+            )));
+        }
+        let items: Vec<Value> = quotes
+            .iter()
+            .map(|quote| {
+                json!({
+                    "object_type": INSTRUMENT,
+                    "object_id": quote.instrument_id,
+                    "operation": operation,
+                })
+            })
+            .collect();
+        let url = &format!("{}{}", ROBINHOOD_API_URL, WATCHLIST_ITEMS_PATH);
+        let payload = json!({ list_id: items });
+        self.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            payload: Some(&payload),
+            url,
+            timeout: None,
+            retry: false,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Moves `symbols` to the top of a watchlist in the given order, the other items
+    /// keep their order below them
+    pub async fn reorder_watchlist(
+        &self,
+        list_id: &str,
+        symbols: &[&str],
+    ) -> Result<(), RobinhoodErr> {
+        let items = self.get_watchlist_items(list_id).await?;
+        let ordered = order_items(&items, symbols).map_err(|symbol| {
+            RobinhoodErr::NotFound(format!(
+                "{}{}{}/ {}",
+                ROBINHOOD_API_URL, WATCHLISTS_PATH, list_id, symbol
+            ))
+        })?;
+        let url = &format!(
+            "{}{}{}/items/reorder/",
+            ROBINHOOD_API_URL, WATCHLISTS_PATH, list_id
+        );
+        let payload = json!({ "item_ids": ordered });
+        self.req(RobinhoodReq {
+            kind: ReqKind::Post,
+            payload: Some(&payload),
+            url,
+            timeout: None,
+            retry: false,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// The instruments of the stock items of a watchlist, in the watchlist order
+    pub async fn resolve_watchlist_items(
+        &self,
+        items: &[WatchlistItem],
+    ) -> Result<Vec<Instrument>, RobinhoodErr> {
+        let ids: Vec<&str> = items
+            .iter()
+            .filter(|item| item.object_type == INSTRUMENT)
+            .map(|item| item.object_id.as_str())
+            .collect();
+        let mut instruments = self.get_instruments(&ids).await?;
+        instruments.sort_by_key(|instrument| ids.iter().position(|id| *id == instrument.id));
+        Ok(instruments)
+    }
+
+    /// Quotes every stock of a watchlist with batched quote requests, in the watchlist order
+    pub async fn get_watchlist_quotes(
+        &self,
+        list_id: &str,
+    ) -> Result<Vec<QuotesResponse>, RobinhoodErr> {
+        let items = self.get_watchlist_items(list_id).await?;
+        // Items usually carry their symbol, the others are resolved first
+        let unresolved: Vec<WatchlistItem> = items
+            .iter()
+            .filter(|item| item.object_type == INSTRUMENT && item.symbol.is_none())
+            .cloned()
+            .collect();
+        let resolved = self.resolve_watchlist_items(&unresolved).await?;
+        let symbols = item_symbols(&items, &resolved);
+        let mut quotes = self.get_quotes(&symbols).await?;
+        quotes.sort_by_key(|quote| {
+            symbols
+                .iter()
+                .position(|symbol| symbol.eq_ignore_ascii_case(&quote.symbol))
+        });
+        Ok(quotes)
+    }
+}
+
+// Symbols of the stock items in the watchlist order, from the item or its resolved instrument
+fn item_symbols<'a>(items: &'a [WatchlistItem], resolved: &'a [Instrument]) -> Vec<&'a str> {
+    items
+        .iter()
+        .filter(|item| item.object_type == INSTRUMENT)
+        .filter_map(|item| {
+            item.symbol.as_deref().or_else(|| {
+                resolved
+                    .iter()
+                    .find(|instrument| instrument.id == item.object_id)
+                    .map(|instrument| instrument.symbol.as_str())
+            })
+        })
+        .collect()
+}
+
+// Item ids with the items of `symbols` first, or the first symbol not in the list
+fn order_items(items: &[WatchlistItem], symbols: &[&str]) -> Result<Vec<String>, String> {
+    let mut ordered = Vec::with_capacity(items.len());
+    for symbol in symbols {
+        match items.iter().find(|item| {
+            item.symbol
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(symbol))
+        }) {
+            // A symbol given twice keeps its first place
+            Some(item) if ordered.contains(&item.id) => {}
+            Some(item) => ordered.push(item.id.clone()),
+            None => return Err(symbol.to_string()),
+        }
+    }
+    for item in items {
+        if !ordered.contains(&item.id) {
+            ordered.push(item.id.clone());
+        }
+    }
+    Ok(ordered)
+}
+
+// "id": "8ce9f983-3cad-4d8c-9eb8-9b9c5b1b8b9f",
+// "display_name": "Screener",
+// "owner_type": "custom",
+// "item_count": 2,
+// "read_only": false,
+// "created_at": "2021-03-04T15:02:11.482381Z",
+// "updated_at": "2021-03-04T15:02:11.719054Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Watchlist {
+    pub id: String,
+    pub display_name: String,
+    pub owner_type: String,
+    pub item_count: usize,
+    pub read_only: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// "id": "5d1f7c3e-2b4a-4e8f-9c6d-1a2b3c4d5e6f",
+// "object_id": "8f92e76f-1e0e-4478-8580-16a6ffcfaef5",
+// "object_type": "instrument",
+// "symbol": "SPY",
+// "created_at": "2021-03-04T15:02:11.482381Z"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchlistItem {
+    pub id: String,
+    /// Instrument id for stocks, currency pair id for crypto
+    pub object_id: String,
+    pub object_type: String,
+    pub symbol: Option<String>,
+    pub created_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, symbol: &str) -> WatchlistItem {
+        WatchlistItem {
+            id: id.to_owned(),
+            object_id: format!("instrument-{}", id),
+            object_type: INSTRUMENT.to_owned(),
+            symbol: Some(symbol.to_owned()),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn reordered_symbols_come_first() {
+        let items = vec![item("1", "SPY"), item("2", "QQQ"), item("3", "IWM")];
+        assert_eq!(
+            order_items(&items, &["iwm", "SPY"]).unwrap(),
+            vec!["3", "1", "2"]
+        );
+        assert_eq!(
+            order_items(&items, &["QQQ", "iwm", "qqq"]).unwrap(),
+            vec!["2", "3", "1"]
+        );
+        assert_eq!(order_items(&items, &["GME"]).unwrap_err(), "GME");
+    }
+
+    #[test]
+    fn symbols_keep_the_watchlist_order() {
+        let mut unresolved = item("2", "QQQ");
+        unresolved.symbol = None;
+        let items = vec![item("1", "SPY"), unresolved, item("3", "IWM")];
+        let qqq: Instrument = serde_json::from_value(json!({
+            "id": "instrument-2", "url": "", "symbol": "QQQ", "name": "Invesco QQQ",
+            "simple_name": null, "tradeable": true, "type": "etp",
+        }))
+        .unwrap();
+        assert_eq!(item_symbols(&items, &[qqq]), vec!["SPY", "QQQ", "IWM"]);
+    }
+}